@@ -1,2 +1,17 @@
 pub mod address;
 pub mod arena;
+pub mod borrow;
+pub mod compact;
+pub mod config;
+pub mod ecs;
+pub mod error;
+pub mod handle;
+#[cfg(feature = "serde")]
+pub mod persist;
+pub mod schema;
+pub mod stats;
+pub mod storage;
+pub mod sync_arena;
+pub mod type_table;
+pub mod typed_arena;
+pub mod view;
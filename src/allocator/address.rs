@@ -1,30 +1,125 @@
-#![forbid(
-    box_pointers,
-    pointer_structural_match,
-    missing_docs,
-    missing_debug_implementations
-)]
-
-use crate::allocator::arena::Arena;
-use std::cell::RefCell;
-use std::marker::PhantomData;
-use std::rc::Rc;
-
-/// Address represents a "pointer" to data in the Arena. Address holds a raw pointer to the arena
-/// for getting entities and also for freeing location.
-#[derive(Clone, Debug)]
+/*!
+This module implements the addresses, which are the "pointers" to entities in the arena.
+
+### Owning and weak addresses
+
+An `Address` owns its entity together with all of its copies. `copy()` and `clone()` both make
+a new owner, and the entity is freed once the last owner is dropped
+```rust
+use arena_allocator::{Address, Arena};
+let mut arena = Arena::default();
+
+struct Dog {name: String}
+
+let bruce = arena.allocate(Dog{name: format!("Bruce")});
+let copied = bruce.copy();
+let cloned = bruce.clone();
+assert_eq!(bruce.ref_count(), 3);
+drop(bruce);
+drop(copied);
+assert_eq!(cloned.get().unwrap().name, "Bruce");
+let weak = cloned.downgrade();
+drop(cloned);
+assert_eq!(weak.get().is_none(), true);
+```
+A `WeakAddress` never keeps the entity alive, and stops resolving once the entity is freed or the
+arena is dropped. Cloning it does not change the count of the entity. It holds on to the list of its
+type so that it can tell when the arena is gone, which is why it is `Clone` but not `Copy`. The
+`Copy` way of pointing to an entity without owning it is a `Handle`, resolved through the arena
+```rust
+use arena_allocator::{Address, Arena, WeakAddress};
+let mut arena = Arena::default();
+
+struct Dog {name: String}
+
+let bruce = arena.allocate(Dog{name: format!("Bruce")});
+let weak: WeakAddress<Dog> = bruce.downgrade();
+let weak_clone = weak.clone();
+drop(weak);
+assert_eq!(weak_clone.get().unwrap().name, "Bruce");
+assert_eq!(bruce.ref_count(), 1);
+
+drop(arena);
+assert!(weak_clone.get().is_none());
+assert!(weak_clone.upgrade().is_none());
+```
+a `WeakAddress` can be upgraded back into an owning `Address` for as long as the entity is alive
+```rust
+use arena_allocator::{Address, Arena};
+let mut arena = Arena::default();
+
+struct Dog {name: String}
+
+let bruce = arena.allocate(Dog{name: format!("Bruce")});
+let weak = bruce.downgrade();
+let upgraded = weak.upgrade().unwrap();
+drop(bruce);
+assert_eq!(weak.get().unwrap().name, "Bruce");
+drop(upgraded);
+assert_eq!(weak.get().is_none(), true);
+assert_eq!(weak.upgrade().is_none(), true);
+```
+`remove()` frees the entity regardless of how many owners it has, which invalidates owning and weak
+addresses alike
+```rust
+use arena_allocator::{Address, Arena};
+let mut arena = Arena::default();
+
+struct Dog {name: String}
+
+let bruce = arena.allocate(Dog{name: format!("Bruce")});
+let cloned = bruce.clone();
+let weak = bruce.downgrade();
+cloned.remove();
+assert_eq!(bruce.get().is_none(), true);
+assert_eq!(weak.get().is_none(), true);
+drop(cloned);
+drop(bruce);
+
+// the freed location is reused, but old addresses do not resolve to the new entity
+let rex = arena.allocate(Dog{name: format!("Rex")});
+assert_eq!(weak.get().is_none(), true);
+assert_eq!(rex.get().unwrap().name, "Rex");
+```
+ */
+
+#![forbid(missing_docs, missing_debug_implementations)]
+
+use crate::allocator::arena::{ArenaId, LocationGroup};
+use crate::allocator::borrow::{found, BorrowError, Ref, RefMut};
+use std::fmt;
+use std::rc::Weak;
+
+/// Address represents a "pointer" to data in the Arena. Address holds a weak reference to the list
+/// of entities of its type, for getting entities and also for freeing location.
+#[derive(Debug)]
 pub struct Address<T: 'static> {
     /// Generation of the address, for an Address to be not None, generation must be the same as
     /// the generation in the target location
     pub generation: usize,
     /// Index of the entities in the array
     pub index: usize,
-    /// This is used to make the Rust compiler be type aware of the entity it is referencing
-    pub phantom: PhantomData<&'static T>,
-    /// Raw pointer to the arena, used for freeing and getting entities
-    pub arena: *mut Arena,
-    /// number of references one address has been copied to
-    pub ref_count: Rc<RefCell<i16>>,
+    /// Id of the arena the entity was allocated in
+    pub arena: ArenaId,
+    /// Weak reference to the list of entities, used for freeing and getting entities. It does not
+    /// keep the list alive, so the address can safely outlive the arena. The number of copies of
+    /// the address is kept in the list as well, next to the entity
+    pub(crate) group: Weak<LocationGroup<T>>,
+}
+
+/// WeakAddress is a non-owning "pointer" to data in the Arena. It does not take part in
+/// reference counting, so it can be freely cloned and never keeps the entity alive
+pub struct WeakAddress<T: 'static> {
+    /// Generation of the address, for an Address to be not None, generation must be the same as
+    /// the generation in the target location
+    pub generation: usize,
+    /// Index of the entities in the array
+    pub index: usize,
+    /// Id of the arena the entity was allocated in
+    pub arena: ArenaId,
+    /// Weak reference to the list of entities, used for getting entities. Like for `Address`, it
+    /// does not keep the list alive
+    pub(crate) group: Weak<LocationGroup<T>>,
 }
 
 impl<T> Drop for Address<T> {
@@ -33,62 +128,155 @@ impl<T> Drop for Address<T> {
     /// it in the arena. This does not guarantee all references will be valid however, because the
     /// remove() method can free an entity while there are other references to the address
     ///
-    /// SAFETY: It is assumed that arena is a valid reference for the entire runtime of the
-    /// program, if this is not the case, dropping an address will cause a segfault
+    /// Dropping an address after its arena was dropped, or after its entity was freed, does
+    /// nothing
     fn drop(&mut self) {
-        let mut v = self.ref_count.borrow_mut();
-        *v -= 1;
-        if *v == 0 {
-            unsafe {
-                let arena: &Arena = &*self.arena;
-                arena.free(&self)
-            };
+        if let Some(group) = self.group.upgrade() {
+            group.drop_owner(self.index, self.generation)
         }
     }
 }
 
 impl<T> Address<T> {
-    /// Get the entity the address is pointing to from the arena. None means the entity was freed
-    /// by something else.
-    ///
-    /// SAFETY: It is assumed that arena is a valid reference for the entire runtime of the
-    /// program, if this is not the case, dropping an address will cause a segfault
-    pub fn get(&self) -> Option<&T> {
-        unsafe {
-            let arena: &Arena = &*self.arena;
-            arena.get(&self)
+    /// Borrow the entity the address is pointing to from the arena. None means the entity was
+    /// freed by something else, or the arena was dropped. Panics if the entity is currently
+    /// mutably borrowed
+    pub fn get(&self) -> Option<Ref<'_, T>> {
+        found(self.try_borrow())
+    }
+
+    /// Mutably borrow the entity the address is pointing to from the arena. None means the entity
+    /// was freed by something else, or the arena was dropped. Panics if the entity is currently
+    /// borrowed
+    pub fn get_mut(&self) -> Option<RefMut<'_, T>> {
+        found(self.try_borrow_mut())
+    }
+
+    /// Borrow the entity the address is pointing to, failing if it was freed or if it is
+    /// currently mutably borrowed
+    pub fn try_borrow(&self) -> Result<Ref<'_, T>, BorrowError> {
+        match self.group.upgrade() {
+            Some(group) => group.borrow(self.index, self.generation),
+            None => Err(BorrowError::Freed),
         }
     }
-    /// Get a mutable reference to entity the address is pointing to from the arena. None means the entity was freed
-    /// by something else.
-    ///
-    /// SAFETY: It is assumed that arena is a valid reference for the entire runtime of the
-    /// program, if this is not the case, dropping an address will cause a segfault
-    pub fn get_mut(&self) -> Option<&mut T> {
-        unsafe {
-            let arena: &mut Arena = &mut *self.arena;
-            arena.get_mut(&self)
+
+    /// Mutably borrow the entity the address is pointing to, failing if it was freed or if it is
+    /// currently borrowed
+    pub fn try_borrow_mut(&self) -> Result<RefMut<'_, T>, BorrowError> {
+        match self.group.upgrade() {
+            Some(group) => group.borrow_mut(self.index, self.generation),
+            None => Err(BorrowError::Freed),
         }
     }
+
+    /// Get a non-owning copy of the Address, which does not keep the entity alive
+    pub fn downgrade(&self) -> WeakAddress<T> {
+        WeakAddress {
+            generation: self.generation,
+            index: self.index,
+            arena: self.arena,
+            group: Weak::clone(&self.group),
+        }
+    }
+
     /// Get a copy of the Address without taking ownership
     pub fn copy(&self) -> Address<T> {
-        *self.ref_count.borrow_mut() += 1;
+        if let Some(group) = self.group.upgrade() {
+            group.add_owner(self.index, self.generation)
+        }
         Address {
             generation: self.generation,
             index: self.index,
-            phantom: PhantomData,
             arena: self.arena,
-            ref_count: Rc::clone(&self.ref_count),
+            group: Weak::clone(&self.group),
         }
     }
 
-    /// Force freeing of an entity regardless of their reference count
+    /// Number of owning addresses of the entity, this one included. 0 once the entity was freed
+    /// or the arena was dropped
+    pub fn ref_count(&self) -> usize {
+        match self.group.upgrade() {
+            Some(group) => group
+                .ref_count(self.index, self.generation)
+                .map_or(0, |count| count.get()),
+            None => 0,
+        }
+    }
+
+    /// Force freeing of an entity regardless of their reference count. The other owners are left
+    /// pointing to a freed entity, and dropping them does nothing
+    /// Panics if the entity is currently borrowed
     pub fn remove(&self) {
-        let mut v = self.ref_count.borrow_mut();
-        *v = -1;
-        unsafe {
-            let arena: &Arena = &*self.arena;
-            arena.free(&self)
-        };
+        if let Some(group) = self.group.upgrade() {
+            group.free(self.index, self.generation)
+        }
+    }
+}
+
+impl<T> Clone for Address<T> {
+    /// Same as `copy()`, the clone is counted as another reference to the entity
+    fn clone(&self) -> Self {
+        self.copy()
+    }
+}
+
+impl<T> WeakAddress<T> {
+    /// Borrow the entity the address is pointing to from the arena. None means the entity was
+    /// freed, or the arena was dropped. Panics if the entity is currently mutably borrowed
+    pub fn get(&self) -> Option<Ref<'_, T>> {
+        found(self.try_borrow())
+    }
+
+    /// Mutably borrow the entity the address is pointing to from the arena. None means the entity
+    /// was freed, or the arena was dropped. Panics if the entity is currently borrowed
+    pub fn get_mut(&self) -> Option<RefMut<'_, T>> {
+        found(self.try_borrow_mut())
+    }
+
+    /// Borrow the entity the address is pointing to, failing if it was freed or if it is
+    /// currently mutably borrowed
+    pub fn try_borrow(&self) -> Result<Ref<'_, T>, BorrowError> {
+        match self.group.upgrade() {
+            Some(group) => group.borrow(self.index, self.generation),
+            None => Err(BorrowError::Freed),
+        }
+    }
+
+    /// Mutably borrow the entity the address is pointing to, failing if it was freed or if it is
+    /// currently borrowed
+    pub fn try_borrow_mut(&self) -> Result<RefMut<'_, T>, BorrowError> {
+        match self.group.upgrade() {
+            Some(group) => group.borrow_mut(self.index, self.generation),
+            None => Err(BorrowError::Freed),
+        }
+    }
+
+    /// Get an owning Address to the entity, which keeps it alive like any other copy.
+    /// None means the entity was freed, or the arena was dropped
+    pub fn upgrade(&self) -> Option<Address<T>> {
+        self.group.upgrade()?.address(self.index, self.generation)
+    }
+}
+
+impl<T> Clone for WeakAddress<T> {
+    /// Unlike cloning an `Address`, the clone is not counted as a reference to the entity
+    fn clone(&self) -> Self {
+        WeakAddress {
+            generation: self.generation,
+            index: self.index,
+            arena: self.arena,
+            group: Weak::clone(&self.group),
+        }
+    }
+}
+
+impl<T> fmt::Debug for WeakAddress<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WeakAddress")
+            .field("generation", &self.generation)
+            .field("index", &self.index)
+            .field("arena", &self.arena)
+            .finish()
     }
 }
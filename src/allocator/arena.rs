@@ -73,11 +73,9 @@ This project is a simple implementation of an Arena allocator, plus a small exam
 file, and some tests comparing the performance. A goal here was to make `Address` which is the "pointer" object in this
 project, to behave just like normal Rust references. With Rust's ownership rules, this is a difficult task to accomplish.
 This is due to the fact that when a pointer needs to be dropped, the pointer must mutate the arena. However, the arena
-is not owned by `Address`, therefore arena needs to use raw pointer dereferencing to do so. This does mean some
-`unsafe` code, but there is some explanation in the code why the unsafe could would not cause any problems, as the only
-time segfaults can happen is when an `Address` is dropped after the arena itself has dropped. Since the arena is essentially
-the memory allocator of the entire application, it must be initialized in the `main()` scope and will be the last thing
-to be dropped in the entire program.
+is not owned by `Address`. To get around this, the list of every type lives in its own reference counted allocation,
+and an `Address` only holds a weak reference to the list of its type. Moving the arena does not move the lists, and
+once the arena is dropped, dropping or reading an `Address` or a `WeakAddress` is a safe no-op.
 This module implements the arena, which is responsible for holding the data.
 
 ### Starting the arena
@@ -102,87 +100,757 @@ values get automatically dropped as well
 use arena_allocator::{Address, Arena};
 let mut arena = Arena::default();
 
-#[derive(Clone)]
 struct Dog {name: String}
 
 let dangling;
 {
     let dog = Dog{name: format!("Bruce")};
     let dog_address = arena.allocate(dog);
-    dangling = dog_address.clone();
+    dangling = dog_address.downgrade();
 }
 let dog = dangling.get();
 assert_eq!(dog.is_none(), true);
+```
+freeing an entity drops it right away, so anything it owns is released as soon as the entity is
+freed rather than when its location gets reused
+```rust
+use arena_allocator::{Address, Arena};
+use std::rc::Rc;
+let mut arena = Arena::default();
+
+struct Dog {toy: Rc<String>}
+
+let toy = Rc::new(format!("Ball"));
+let dog_address = arena.allocate(Dog{toy: Rc::clone(&toy)});
+assert_eq!(Rc::strong_count(&toy), 2);
+dog_address.remove();
+assert_eq!(Rc::strong_count(&toy), 1);
+```
+
+### Allocating in bulk
+
+Many entities can be added at once, which fills freed locations first and makes room for the rest
+in one go
+```rust
+use arena_allocator::{Address, Arena};
+let mut arena = Arena::default();
+
+struct Dog {name: String}
+
+let mut dogs = arena.allocate_many(vec![Dog{name: format!("Bruce")}, Dog{name: format!("Rex")}]);
+dogs.remove(0);
+let puppies = arena.allocate_from_fn(3, |i| Dog{name: format!("Puppy #{}", i)});
+assert_eq!(puppies.iter().map(|dog| dog.index).collect::<Vec<_>>(), vec![0, 2, 3]);
+assert_eq!(puppies[0].get().unwrap().name, "Puppy #0");
+assert_eq!(dogs[0].get().unwrap().name, "Rex");
+```
+
+### Clearing entities
+
+Every entity of a type, or of the whole arena, can be freed at once without going through their
+addresses. Addresses to them stop resolving, just like after freeing each of them
+```rust
+use arena_allocator::{Address, Arena};
+let mut arena = Arena::default();
+
+struct Dog {name: String}
+struct Cat {name: String}
+
+let dogs = arena.allocate_from_fn(3, |i| Dog{name: format!("Dog #{}", i)});
+let tom = arena.allocate(Cat{name: format!("Tom")});
+arena.clear::<Dog>();
+assert!(dogs.iter().all(|dog| dog.get().is_none()));
+assert_eq!(tom.get().unwrap().name, "Tom");
+// freed locations are reused, lowest index first
+assert_eq!(arena.allocate(Dog{name: format!("Rex")}).index, 0);
+
+arena.clear_all();
+assert!(tom.get().is_none());
+
+// dropping the whole list of a type
+let felix = arena.allocate(Cat{name: format!("Felix")});
+let weak = felix.downgrade();
+assert!(arena.remove_type::<Cat>());
+assert!(arena.stats().of::<Cat>().is_none());
+assert!(felix.get().is_none() && weak.get().is_none());
+assert!(!arena.remove_type::<Cat>());
+```
+
+### Iterating over entities
+
+All live entities of a type can be walked in the order they sit in the arena. Freed locations are
+skipped, and every entity comes with an `Address` to it
+```rust
+use arena_allocator::{Address, Arena};
+let mut arena = Arena::default();
+
+struct Dog {name: String, age: u8}
+
+let bruce = arena.allocate(Dog{name: format!("Bruce"), age: 3});
+let rex = arena.allocate(Dog{name: format!("Rex"), age: 5});
+let max = arena.allocate(Dog{name: format!("Max"), age: 1});
+rex.remove();
+
+for (_, mut dog) in arena.iter_mut::<Dog>() {
+    dog.age += 1;
+}
+let names: Vec<String> = arena.iter::<Dog>().map(|(_, dog)| dog.name.clone()).collect();
+assert_eq!(names, vec![format!("Bruce"), format!("Max")]);
+
+let (address, _) = arena.iter::<Dog>().next().unwrap();
+assert_eq!(address.get().unwrap().age, 4);
+```
+`retain()` walks the entities the same way, and frees the ones the predicate returns false for
+```rust
+use arena_allocator::{Address, Arena};
+let mut arena = Arena::default();
+
+struct Monster {health: i32}
+
+let monsters = arena.allocate_many(vec![-5, 10, 0, 3].into_iter().map(|health| Monster{health}));
+let removed = arena.retain::<Monster>(|_, monster| {
+    monster.health -= 1;
+    monster.health > 0
+});
+assert_eq!(removed, 2);
+assert!(monsters[0].get().is_none());
+assert_eq!(monsters[1].get().unwrap().health, 9);
+assert_eq!(arena.iter::<Monster>().count(), 2);
+```
+
+### Moving and dropping the arena
+
+The arena can be moved around freely after entities were allocated in it, and addresses that outlive
+it simply stop resolving. A borrow of an entity keeps its list alive until the borrow ends
+```rust
+use arena_allocator::{Address, Arena};
+
+struct Dog {name: String}
+
+fn make_arena() -> (Arena, Address<Dog>) {
+    let mut arena = Arena::default();
+    let dog_address = arena.allocate(Dog{name: format!("Bruce")});
+    (arena, dog_address)
+}
+
+let (arena, dog_address) = make_arena();
+let arenas = vec![arena];
+let dog = dog_address.get().unwrap();
+drop(arenas);
+assert_eq!(dog.name, "Bruce");
+drop(dog);
+assert_eq!(dog_address.get().is_none(), true);
 ```
  */
 
-#![forbid(
-    box_pointers,
-    pointer_structural_match,
-    missing_docs,
-    missing_debug_implementations
-)]
-
-use std::any::TypeId;
-use std::cell::RefCell;
-use std::collections::hash_map::DefaultHasher;
-use std::hash::{Hash, Hasher};
-use std::marker::PhantomData;
-use std::rc::Rc;
+#![forbid(missing_docs, missing_debug_implementations)]
 
-use anymap;
+use std::any::{self, TypeId};
+use std::cell::{self, Cell, RefCell, UnsafeCell};
+use std::collections::HashMap;
+use std::fmt;
+use std::marker::PhantomData;
+use std::mem;
+use std::rc::{Rc, Weak};
+use std::sync::atomic::{AtomicU64, Ordering};
 
 use super::address::Address;
-
-static DEFAULT_CAPACITY: usize = 16;
+use super::borrow::{found, BorrowError, Ref, RefMut};
+use super::compact::{Moved, Remap};
+use super::config::{ArenaBuilder, ArenaConfig};
+use super::error::ArenaError;
+use super::handle::{Handle, Locator};
+use super::stats::{ArenaStats, TypeStats};
+use super::storage::Locations;
+use super::type_table::TypeTable;
+use super::view::ArenaView;
 
 /// Struct that holds the collection of objects
-/// uses `anymap` to store the types of the objects being stored, and
-/// look up the list of types
+/// uses a table of the types of the objects being stored to look up the list of each type, see
+/// `TypeTable`
 #[derive(Debug)]
 pub struct Arena {
-    data: anymap::Map,
-    capacity: usize,
-    freed_groups: Vec<u64>,
+    /// The group of every type, in the order they were created
+    groups: TypeTable,
+    pub(crate) config: ArenaConfig,
+    /// Capacities of the types that do not use the one in `config`
+    pub(crate) type_capacities: HashMap<TypeId, usize>,
+    id: ArenaId,
 }
 
-/// A LocationGroup is the entity that holds the array of entities and maintains a list of all
-/// indexes that have been freed and can be reused
-struct LocationGroup<T> {
-    locations: Vec<Location<T>>,
-    free_indexes: RefCell<Vec<usize>>,
-    arena: *mut Arena,
-    type_id_hash: u64,
+/// Id of an arena, unique for the whole program. Every address carries the id of the arena it
+/// was allocated in, so that it can not be used with another arena by mistake
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ArenaId(u64);
+
+/// The parts of a group that do not depend on the type of its entities
+pub(crate) trait AnyGroup {
+    /// Statistics of the entities in the group
+    fn stats(&self) -> TypeStats;
+
+    /// Whether any entity of the group is currently borrowed
+    fn has_borrows(&self) -> bool;
+
+    /// Whether the entity at an index is currently borrowed, if it is still of the given
+    /// generation
+    fn has_borrow(&self, index: usize, generation: usize) -> bool;
+
+    /// Frees every entity of the group, as if each was freed on its own
+    ///
+    /// Panics if any entity of the group is borrowed
+    fn clear(&self);
+
+    /// Frees the entity at an index if it is still of the given generation, see
+    /// `LocationGroup::free()`
+    fn free(&self, index: usize, generation: usize);
+}
+
+/// A LocationGroup is the entity that holds the array of entities. Locations that have been freed
+/// and can be reused are chained together through their vacant slots, `first_free` being the head
+/// of that list
+///
+/// Every group lives in its own `Rc`, owned by the arena. Addresses hold weak references to it, so
+/// they can outlive the arena and survive it being moved. `this` is a weak reference of the group
+/// to itself, used for handing out new addresses
+///
+/// `generation_floor` is the generation new locations start at. Compacting removes locations from
+/// the end of the list, addresses to them must not resolve once the list grows back.
+/// `max_generation` is the generation after which locations are retired. `arena` is the id of the
+/// arena that owns the group, stamped into every address it hands out. A group that can not
+/// `grow` never reallocates its list, and neither does a paged one (see `Storage`), so entities
+/// never move
+pub(crate) struct LocationGroup<T> {
+    locations: UnsafeCell<Locations<T>>,
+    pub(crate) first_free: Cell<Option<usize>>,
+    pub(crate) generation_floor: Cell<usize>,
+    pub(crate) max_generation: Cell<usize>,
+    pub(crate) arena: ArenaId,
+    pub(crate) grow: Cell<bool>,
+    this: Weak<LocationGroup<T>>,
 }
 
 /// Location represents an index inside the array of entities
 /// the purpose of this is maintaining a generation, so that all entity lookups can know
 /// if the entity is the one they are looking for
-/// `RefCell` used to provide a safe way to drop values from the arena
-/// without taking a mutable reference
-#[derive(Clone, Debug)]
-struct Location<T> {
-    generation: RefCell<usize>,
-    entity: T,
+/// `Cell` and `RefCell` used to provide a safe way to drop values from the arena
+/// without taking a mutable reference. The `RefCell` around the slot doubles as the borrow flag
+/// of the entity
+/// The number of owning addresses of the current entity is kept here as well, next to its
+/// generation, so that copying and dropping addresses never allocates. It is only meaningful while
+/// the location holds an entity
+#[derive(Debug)]
+pub(crate) struct Location<T> {
+    pub(crate) generation: Cell<usize>,
+    pub(crate) ref_count: Cell<usize>,
+    pub(crate) slot: RefCell<Slot<T>>,
+}
+
+/// What a location currently holds. A vacant slot holds the index of the next vacant location,
+/// if any. A retired slot ran out of generations and is never used again
+#[derive(Debug)]
+pub(crate) enum Slot<T> {
+    Occupied(T),
+    Vacant { next_free: Option<usize> },
+    Retired,
+}
+
+/// Iterator over all live entities of a single type, created by `Arena::iter()`
+/// yields the address of every entity along with a shared borrow of it
+pub struct Iter<'a, T: 'static> {
+    group: Option<Rc<LocationGroup<T>>>,
+    next: usize,
+    phantom: PhantomData<&'a Arena>,
+}
+
+/// Iterator over all live entities of a single type, created by `Arena::iter_mut()`
+/// yields the address of every entity along with a mutable borrow of it
+pub struct IterMut<'a, T: 'static> {
+    group: Option<Rc<LocationGroup<T>>>,
+    next: usize,
+    phantom: PhantomData<&'a mut Arena>,
 }
 
 impl<T> LocationGroup<T> {
-    fn new(capacity: usize, arena_ptr: *mut Arena, type_id_hash: u64) -> LocationGroup<T> {
-        LocationGroup {
-            locations: Vec::<Location<T>>::with_capacity(capacity),
-            free_indexes: RefCell::new(Vec::<usize>::with_capacity(capacity)),
-            arena: arena_ptr,
-            type_id_hash,
+    pub(crate) fn new(
+        config: &ArenaConfig,
+        capacity: usize,
+        arena: ArenaId,
+    ) -> Rc<LocationGroup<T>> {
+        Rc::new_cyclic(|this| LocationGroup {
+            locations: UnsafeCell::new(Locations::new(config.storage, capacity)),
+            first_free: Cell::new(None),
+            generation_floor: Cell::new(0),
+            max_generation: Cell::new(config.max_generation()),
+            arena,
+            grow: Cell::new(config.grow),
+            this: Weak::clone(this),
+        })
+    }
+
+    /// All the locations of the group
+    pub(crate) fn locations(&self) -> &Locations<T> {
+        unsafe { &*self.locations.get() }
+    }
+
+    /// The list of locations, for adding new ones
+    ///
+    /// SAFETY: the caller must hold the arena mutably, so that no iterator or other reference to
+    /// the list itself is alive. References to single locations can be alive through borrow
+    /// guards, contiguous locations must not be reallocated while they are (see `is_borrowed()`).
+    /// Checks that go through `locations()` have to be done before taking the list mutably
+    #[allow(clippy::mut_from_ref)]
+    pub(crate) unsafe fn locations_mut(&self) -> &mut Locations<T> {
+        &mut *self.locations.get()
+    }
+
+    /// Whether any entity of the group is currently borrowed
+    fn is_borrowed(&self) -> bool {
+        self.locations()
+            .iter()
+            .any(|location| location.slot.try_borrow_mut().is_err())
+    }
+
+    /// Borrow the entity at an index, if it is still of the given generation.
+    /// The guard keeps the group alive, so it is free to outlive both the arena and the address
+    /// it was borrowed through
+    pub(crate) fn borrow<'a>(
+        self: Rc<Self>,
+        index: usize,
+        generation: usize,
+    ) -> Result<Ref<'a, T>, BorrowError> {
+        // SAFETY: the group is kept alive by the Rc moved into the guard, which drops the borrow
+        // before the Rc
+        let group: &'a LocationGroup<T> = unsafe { &*Rc::as_ptr(&self) };
+        let location = group.locations().get(index).ok_or(BorrowError::Freed)?;
+        if location.generation.get() != generation {
+            return Err(BorrowError::Freed);
+        }
+        let slot = location
+            .slot
+            .try_borrow()
+            .map_err(|_| BorrowError::AlreadyBorrowed)?;
+        match cell::Ref::filter_map(slot, |slot| match slot {
+            Slot::Occupied(entity) => Some(entity),
+            Slot::Vacant { .. } | Slot::Retired => None,
+        }) {
+            Ok(entity) => Ok(Ref::new(entity, self)),
+            Err(_) => Err(BorrowError::Freed),
+        }
+    }
+
+    /// Mutably borrow the entity at an index, if it is still of the given generation.
+    /// The guard keeps the group alive, so it is free to outlive both the arena and the address
+    /// it was borrowed through
+    pub(crate) fn borrow_mut<'a>(
+        self: Rc<Self>,
+        index: usize,
+        generation: usize,
+    ) -> Result<RefMut<'a, T>, BorrowError> {
+        // SAFETY: the group is kept alive by the Rc moved into the guard, which drops the borrow
+        // before the Rc
+        let group: &'a LocationGroup<T> = unsafe { &*Rc::as_ptr(&self) };
+        let location = group.locations().get(index).ok_or(BorrowError::Freed)?;
+        if location.generation.get() != generation {
+            return Err(BorrowError::Freed);
+        }
+        let slot = location
+            .slot
+            .try_borrow_mut()
+            .map_err(|_| BorrowError::AlreadyBorrowed)?;
+        match cell::RefMut::filter_map(slot, |slot| match slot {
+            Slot::Occupied(entity) => Some(entity),
+            Slot::Vacant { .. } | Slot::Retired => None,
+        }) {
+            Ok(entity) => Ok(RefMut::new(entity, self)),
+            Err(_) => Err(BorrowError::Freed),
+        }
+    }
+
+    /// The reference count of the entity at an index, if it is still of the given generation
+    pub(crate) fn ref_count(&self, index: usize, generation: usize) -> Option<&Cell<usize>> {
+        let location = self.locations().get(index)?;
+        if location.generation.get() != generation || !location.is_occupied() {
+            return None;
+        }
+        Some(&location.ref_count)
+    }
+
+    /// Counts one more owner of the entity at an index, if it is still of the given generation
+    pub(crate) fn add_owner(&self, index: usize, generation: usize) {
+        if let Some(count) = self.ref_count(index, generation) {
+            count.set(count.get() + 1);
+        }
+    }
+
+    /// Counts one less owner of the entity at an index, if it is still of the given generation,
+    /// and frees it once it has none left
+    ///
+    /// Panics if the entity is freed while it is borrowed
+    pub(crate) fn drop_owner(&self, index: usize, generation: usize) {
+        if let Some(count) = self.ref_count(index, generation) {
+            count.set(count.get().saturating_sub(1));
+            if count.get() == 0 {
+                self.free(index, generation);
+            }
+        }
+    }
+
+    /// Index and generation of an address, checking that it was allocated in the arena of the
+    /// group and that its index is in range
+    pub(crate) fn locate(&self, address: &impl Locator<T>) -> Result<(usize, usize), ArenaError> {
+        check_arena(self.arena, address)?;
+        let (index, generation) = address.locate();
+        let len = self.locations().len();
+        if index >= len {
+            return Err(ArenaError::IndexOutOfRange { index, len });
+        }
+        Ok((index, generation))
+    }
+
+    /// Creates a new address for the entity at an index, if it is still of the given generation.
+    /// This increments the reference count shared with the entity's other addresses
+    pub(crate) fn address(&self, index: usize, generation: usize) -> Option<Address<T>> {
+        let count = self.ref_count(index, generation)?;
+        count.set(count.get() + 1);
+        Some(Address {
+            generation,
+            index,
+            arena: self.arena,
+            group: Weak::clone(&self.this),
+        })
+    }
+
+    /// Adds a new entity to the group and returns the address to that entity
+    ///
+    /// SAFETY: the caller must hold the arena mutably, see `locations_mut()`
+    /// Panics if the group is full and not allowed to grow
+    pub(crate) unsafe fn allocate(&self, v: T) -> Address<T> {
+        match self.try_allocate(v) {
+            Ok(address) => address,
+            Err(_) => panic!("the arena is full and not allowed to grow"),
+        }
+    }
+
+    /// Adds a new entity to the group and returns the address to that entity. Gives the entity
+    /// back if the group is full and not allowed to grow
+    ///
+    /// SAFETY: the caller must hold the arena mutably, see `locations_mut()`
+    pub(crate) unsafe fn try_allocate(&self, v: T) -> Result<Address<T>, T> {
+        let (index, generation) = self.try_insert(v)?;
+        Ok(Address::<T> {
+            generation,
+            index,
+            arena: self.arena,
+            group: Weak::clone(&self.this),
+        })
+    }
+
+    /// Adds a new entity to the group with a reference count of one, and returns its index and
+    /// generation without making an address to it. Gives the entity back if the group is full and
+    /// not allowed to grow
+    ///
+    /// SAFETY: the caller must hold the arena mutably, see `locations_mut()`
+    pub(crate) unsafe fn try_insert(&self, v: T) -> Result<(usize, usize), T> {
+        let (generation, index): (usize, usize);
+        match self.first_free.get() {
+            Some(idx) => {
+                // only this vacant location is touched, other locations may be borrowed
+                let location = &mut *self.locations_mut().location_ptr(idx);
+                let slot = mem::replace(location.slot.get_mut(), Slot::Occupied(v));
+                if let Slot::Vacant { next_free } = slot {
+                    self.first_free.set(next_free);
+                }
+                location.ref_count.set(1);
+                generation = location.generation.get();
+                index = idx;
+            }
+            None => {
+                let locations = self.locations();
+                let full = locations.len() == locations.capacity();
+                if full && !self.grow.get() {
+                    return Err(v);
+                }
+                // growing the list moves every entity, which would leave borrows of them dangling
+                if full && !locations.is_stable() && self.is_borrowed() {
+                    panic!("can not grow the arena while its entities are borrowed");
+                }
+                generation = self.generation_floor.get();
+                index = locations.len();
+                // the list is only taken mutably once the checks above are done with it
+                self.locations_mut().push(Location {
+                    generation: Cell::new(generation),
+                    ref_count: Cell::new(1),
+                    slot: RefCell::new(Slot::Occupied(v)),
+                })
+            }
+        };
+        Ok((index, generation))
+    }
+
+    /// Checks that the list can be reallocated, which moves every location in it unless it is
+    /// paged
+    ///
+    /// Panics if any entity is borrowed, or if the group is not allowed to grow and the list is
+    /// not empty
+    fn before_moving(&self) {
+        if self.locations().is_empty() || self.locations().is_stable() {
+            return;
+        }
+        if !self.grow.get() {
+            panic!("can not move the entities of an arena that is not allowed to grow");
+        }
+        if self.is_borrowed() {
+            panic!("can not move the entities of the arena while they are borrowed");
+        }
+    }
+
+    /// Adds every entity of an iterator to the group, filling vacant locations first. Room for
+    /// the rest is made at once, from the lower bound of the iterator's size
+    ///
+    /// SAFETY: the caller must hold the arena mutably, see `locations_mut()`
+    pub(crate) unsafe fn allocate_many(
+        &self,
+        values: impl IntoIterator<Item = T>,
+    ) -> Vec<Address<T>> {
+        let mut values = values.into_iter();
+        let mut addresses = Vec::with_capacity(values.size_hint().0);
+        while let Some(v) = values.next() {
+            if self.first_free.get().is_none() {
+                let locations = self.locations();
+                let wanted = values.size_hint().0 + 1;
+                if self.grow.get() && locations.capacity() - locations.len() < wanted {
+                    // growing the list moves every entity, see `allocate()`
+                    if !locations.is_stable() && self.is_borrowed() {
+                        panic!("can not grow the arena while its entities are borrowed");
+                    }
+                    self.locations_mut().reserve(wanted);
+                }
+            }
+            addresses.push(self.allocate(v));
+        }
+        addresses
+    }
+
+    /// Mark the location at an index as free, if it is still of the given generation. The entity
+    /// is dropped right away. A location whose generation can not go up anymore is retired
+    /// instead, so that old addresses to it never resolve again
+    ///
+    /// Panics if the entity is currently borrowed
+    pub(crate) fn free(&self, index: usize, generation: usize) {
+        if let Err(BorrowError::AlreadyBorrowed) = self.try_free(index, generation) {
+            panic!("can not free an entity while it is borrowed")
+        }
+    }
+
+    /// Same as `free()`, but fails instead of panicking if the entity is borrowed. Fails with
+    /// `BorrowError::Freed` if there is no entity of the given generation at the index
+    pub(crate) fn try_free(&self, index: usize, generation: usize) -> Result<(), BorrowError> {
+        // the entity is dropped only once the location is vacant, its destructor may drop
+        // addresses that free other entities of this same group
+        self.take(index, generation).map(drop)
+    }
+
+    /// Same as `try_free()`, but moves the entity out of the group instead of dropping it
+    pub(crate) fn take(&self, index: usize, generation: usize) -> Result<T, BorrowError> {
+        let location = self.locations().get(index).ok_or(BorrowError::Freed)?;
+        if location.generation.get() != generation || !location.is_occupied() {
+            return Err(BorrowError::Freed);
+        }
+        let mut slot = location
+            .slot
+            .try_borrow_mut()
+            .map_err(|_| BorrowError::AlreadyBorrowed)?;
+        let retired = generation >= self.max_generation.get();
+        let emptied = if retired {
+            Slot::Retired
+        } else {
+            Slot::Vacant {
+                next_free: self.first_free.get(),
+            }
+        };
+        let freed = mem::replace(&mut *slot, emptied);
+        drop(slot);
+        if !retired {
+            self.first_free.set(Some(index));
+            location.generation.set(generation + 1);
+        }
+        match freed {
+            Slot::Occupied(entity) => Ok(entity),
+            Slot::Vacant { .. } | Slot::Retired => unreachable!("the location held an entity"),
         }
     }
 }
 
-impl<T> Drop for LocationGroup<T> {
-    fn drop(&mut self) {
-        unsafe {
-            let arena: &mut Arena = &mut *self.arena;
-            arena.freed_groups.push(self.type_id_hash)
+impl<T> LocationGroup<T> {
+    /// Moves every entity to the first vacant location before it, so that all entities are at
+    /// the front of the list, then drops the vacant locations left at the end. Retired locations
+    /// stay where they are. Returns where each moved entity went, by the index and generation it
+    /// was moved from
+    ///
+    /// Moved entities take their reference count along. Only the addresses that get updated
+    /// through the returned table count toward it, dropping the others does nothing
+    ///
+    /// A group that can not grow promises its entities never move, and is left as is
+    ///
+    /// SAFETY: the caller must hold the arena mutably, see `locations_mut()`
+    /// Panics if any entity of the group is borrowed
+    pub(crate) unsafe fn compact(&self) -> HashMap<(usize, usize), Moved> {
+        if !self.grow.get() {
+            return HashMap::new();
+        }
+        if self.is_borrowed() {
+            panic!("can not compact the arena while its entities are borrowed");
+        }
+        let locations = self.locations_mut();
+        let mut moved = HashMap::new();
+        let (mut front, mut back) = (0, locations.len());
+        loop {
+            while front < back && !locations[front].is_vacant() {
+                front += 1;
+            }
+            while back > front && !locations[back - 1].is_occupied() {
+                back -= 1;
+            }
+            if front == back {
+                break;
+            }
+            // front is vacant and back - 1 holds an entity, so front < back - 1
+            back -= 1;
+            let (to, from) = locations.pair_mut(front, back);
+            let old_generation = from.generation.get();
+            // the location it is moved from is emptied just like freeing it would
+            let emptied = if old_generation >= self.max_generation.get() {
+                Slot::Retired
+            } else {
+                from.generation.set(old_generation + 1);
+                Slot::Vacant { next_free: None }
+            };
+            *to.slot.get_mut() = mem::replace(from.slot.get_mut(), emptied);
+            to.ref_count.set(from.ref_count.get());
+            moved.insert(
+                (back, old_generation),
+                Moved {
+                    index: front,
+                    generation: to.generation.get(),
+                },
+            );
+        }
+        let mut floor = self.generation_floor.get();
+        while let Some(location) = locations.last() {
+            if !location.is_vacant() {
+                break;
+            }
+            floor = floor.max(location.generation.get());
+            locations.pop();
+        }
+        self.generation_floor.set(floor);
+        if self.grow.get() {
+            locations.shrink_to_fit();
+        }
+        // vacant locations can be left before retired ones, lowest index first
+        let mut first_free = None;
+        for index in (0..locations.len()).rev() {
+            if let Slot::Vacant { next_free } = locations[index].slot.get_mut() {
+                *next_free = first_free;
+                first_free = Some(index);
+            }
+        }
+        self.first_free.set(first_free);
+        moved
+    }
+}
+
+impl<T: 'static> AnyGroup for LocationGroup<T> {
+    fn stats(&self) -> TypeStats {
+        let locations = self.locations();
+        let count = |test: fn(&Location<T>) -> bool| {
+            locations.iter().filter(|location| test(location)).count()
         };
+        let (live, freed) = (count(Location::is_occupied), count(Location::is_vacant));
+        let capacity = locations.capacity();
+        TypeStats {
+            live,
+            freed,
+            retired: locations.len() - live - freed,
+            slots: locations.len(),
+            capacity,
+            max_generation: locations
+                .iter()
+                .map(|location| location.generation.get())
+                .max()
+                .unwrap_or(0),
+            bytes: capacity * mem::size_of::<Location<T>>(),
+            ..TypeStats::new::<T>()
+        }
+    }
+
+    fn has_borrows(&self) -> bool {
+        self.is_borrowed()
+    }
+
+    fn has_borrow(&self, index: usize, generation: usize) -> bool {
+        self.locations().get(index).is_some_and(|location| {
+            location.generation.get() == generation && location.slot.try_borrow_mut().is_err()
+        })
+    }
+
+    fn free(&self, index: usize, generation: usize) {
+        LocationGroup::free(self, index, generation)
+    }
+
+    fn clear(&self) {
+        if self.is_borrowed() {
+            panic!("can not clear the arena while its entities are borrowed");
+        }
+        let max_generation = self.max_generation.get();
+        let mut freed = Vec::new();
+        // vacant locations are chained back together, lowest index first
+        let mut first_free = None;
+        for (index, location) in self.locations().iter().enumerate().rev() {
+            let mut slot = location.slot.borrow_mut();
+            if let Slot::Occupied(_) = *slot {
+                let generation = location.generation.get();
+                let emptied = if generation >= max_generation {
+                    Slot::Retired
+                } else {
+                    location.generation.set(generation + 1);
+                    Slot::Vacant { next_free: None }
+                };
+                freed.push(mem::replace(&mut *slot, emptied));
+            }
+            if let Slot::Vacant { next_free } = &mut *slot {
+                *next_free = first_free;
+                first_free = Some(index);
+            }
+        }
+        self.first_free.set(first_free);
+        // entities are dropped once every location is emptied, see `free()`
+        drop(freed);
+    }
+}
+
+impl fmt::Debug for dyn AnyGroup {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.stats().type_name)
+    }
+}
+
+impl<T> Location<T> {
+    /// Whether the location holds an entity. A slot that is borrowed must hold one, as vacant
+    /// slots are never handed out
+    fn is_occupied(&self) -> bool {
+        match self.slot.try_borrow() {
+            Ok(slot) => matches!(*slot, Slot::Occupied(_)),
+            Err(_) => true,
+        }
+    }
+
+    /// Whether the location is free to hold a new entity
+    fn is_vacant(&self) -> bool {
+        match self.slot.try_borrow() {
+            Ok(slot) => matches!(*slot, Slot::Vacant { .. }),
+            Err(_) => false,
+        }
     }
 }
 
@@ -190,115 +858,440 @@ impl Arena {
     /// Creates a new arena with a given capacity.
     /// The capacity dictates the initial size of all arrays created for each entity
     pub fn new(capacity: usize) -> Arena {
-        Arena {
-            data: anymap::AnyMap::new(),
+        Arena::with_config(ArenaConfig {
             capacity,
-            freed_groups: Vec::new(),
-        }
+            ..ArenaConfig::default()
+        })
     }
 
-    /// Get a reference to the entity at a given address
-    /// This method borrows the arena, so all rust borrowing rules apply to all entities in the
-    /// allocator
+    /// Creates a new arena with the given settings
     ///
-    /// unwrap() use is safe here as it is impossible to have an Address without adding an entity
-    /// for the type it is referencing. Therefore, unwrap() will never be called on None
-    #[inline]
-    pub fn get<T: 'static>(&self, address: &Address<T>) -> Option<&T> {
-        let list = &self.data.get::<LocationGroup<T>>().unwrap().locations;
-        let item = &list[address.index];
-        if *item.generation.borrow() == address.generation {
-            Some(&item.entity)
-        } else {
-            None
+    /// Panics if `generation_bits` is 0 or more than the bits of a `usize`, or if the storage is
+    /// paged with pages of 0 locations
+    pub fn with_config(config: ArenaConfig) -> Arena {
+        config.check();
+        Arena {
+            groups: TypeTable::new(),
+            config,
+            type_capacities: HashMap::new(),
+            id: ArenaId::next(),
         }
     }
 
-    /// Get a mutable reference to the entity at a given address
-    /// This method borrows the arena, so all rust borrowing rules apply to all entities in the
-    /// allocator
-    /// prefer Address.get_mut() to this for mutations that are deeply nested in already borrowed
-    /// entities. Rust's borrow rules are incompatible with the idea of arena allocation
-    /// where all objects live forever therefore unsafe code of Address.get_mut() is necessary
+    /// Starts building an arena, for setting the capacity of each type on its own
+    pub fn builder() -> ArenaBuilder {
+        ArenaBuilder::new()
+    }
+
+    /// The id of the arena, carried by every address allocated in it
+    pub fn id(&self) -> ArenaId {
+        self.id
+    }
+
+    /// Finds the group and location an address points to, checking that the address was
+    /// allocated in this arena and that its index is in range
+    fn locate<T: 'static>(
+        &self,
+        address: &impl Locator<T>,
+    ) -> Result<(Rc<LocationGroup<T>>, usize, usize), ArenaError> {
+        check_arena(self.id, address)?;
+        let group = self.find_group::<T>().ok_or(ArenaError::UnknownType {
+            type_name: any::type_name::<T>(),
+        })?;
+        let (index, generation) = group.locate(address)?;
+        Ok((Rc::clone(group), index, generation))
+    }
+
+    /// The group holding entities of a type
     ///
     /// unwrap() use is safe here as it is impossible to have an Address without adding an entity
     /// for the type it is referencing. Therefore, unwrap() will never be called on None
     #[inline]
-    pub fn get_mut<T: 'static>(&mut self, address: &Address<T>) -> Option<&mut T> {
-        let list = &mut self.data.get_mut::<LocationGroup<T>>().unwrap().locations;
-        let item = &mut list[address.index];
-        if *item.generation.borrow() == address.generation {
-            Some(&mut item.entity)
-        } else {
-            None
+    fn group<T: 'static>(&self) -> &Rc<LocationGroup<T>> {
+        self.find_group::<T>().unwrap()
+    }
+
+    /// The group holding entities of a type, if any entity of the type was allocated
+    #[inline]
+    pub(crate) fn find_group<T: 'static>(&self) -> Option<&Rc<LocationGroup<T>>> {
+        self.groups.get::<T>()
+    }
+
+    /// The group holding entities of a type, without knowing the type
+    pub(crate) fn find_any_group(&self, type_id: TypeId) -> Option<&Rc<dyn AnyGroup>> {
+        self.groups.get_any(type_id)
+    }
+
+    /// The group holding entities of a type, created if no entity of the type was allocated yet
+    #[inline]
+    pub(crate) fn group_or_insert<T: 'static>(&mut self) -> &Rc<LocationGroup<T>> {
+        if self.find_group::<T>().is_none() {
+            let capacity = match self.type_capacities.get(&TypeId::of::<T>()) {
+                Some(&capacity) => capacity,
+                None => self.config.capacity,
+            };
+            let group = LocationGroup::<T>::new(&self.config, capacity, self.id);
+            self.groups.insert(group);
         }
+        self.group::<T>()
+    }
+
+    /// A view of the entities of a single type, which finds their list once instead of on every
+    /// access. The list is created if no entity of the type was allocated yet
+    pub fn view<T: 'static>(&mut self) -> ArenaView<'_, T> {
+        ArenaView::new(self.group_or_insert::<T>())
+    }
+
+    /// Borrow the entity at a given address or handle. None means the entity was freed.
+    /// Entities keep their own borrow flags, the entity can be borrowed any number of times
+    /// but panics if it is currently mutably borrowed, or if the address belongs to another arena
+    #[inline]
+    pub fn get<T: 'static>(&self, address: impl Locator<T>) -> Option<Ref<'_, T>> {
+        ArenaError::found(self.try_get(address))
+    }
+
+    /// Mutably borrow the entity at a given address or handle. None means the entity was freed.
+    /// Panics if the entity is currently borrowed through one of its addresses, or if the address
+    /// belongs to another arena
+    #[inline]
+    pub fn get_mut<T: 'static>(&mut self, address: impl Locator<T>) -> Option<RefMut<'_, T>> {
+        ArenaError::found(self.try_get_mut(address))
+    }
+
+    /// Borrow the entity at a given address or handle, failing with the reason it could not be
+    /// borrowed instead of panicking
+    pub fn try_get<T: 'static>(&self, address: impl Locator<T>) -> Result<Ref<'_, T>, ArenaError> {
+        let (group, index, generation) = self.locate(&address)?;
+        group
+            .borrow(index, generation)
+            .map_err(|error| ArenaError::from_borrow(error, index, generation))
+    }
+
+    /// Mutably borrow the entity at a given address or handle, failing with the reason it could
+    /// not be borrowed instead of panicking
+    pub fn try_get_mut<T: 'static>(
+        &mut self,
+        address: impl Locator<T>,
+    ) -> Result<RefMut<'_, T>, ArenaError> {
+        let (group, index, generation) = self.locate(&address)?;
+        group
+            .borrow_mut(index, generation)
+            .map_err(|error| ArenaError::from_borrow(error, index, generation))
+    }
+
+    /// Get a new address to the entity at a given handle, which counts as a copy of the entity's
+    /// other addresses. None means the entity was freed, or that the handle belongs to another
+    /// arena
+    pub fn address<T: 'static>(&self, handle: Handle<T>) -> Option<Address<T>> {
+        let (group, index, generation) = self.locate(&handle).ok()?;
+        group.address(index, generation)
     }
 
     /// Adds a new entity to the arena and returns the address to that entity
+    ///
+    /// Panics if the entities of this type have to be moved to make room while some of them are
+    /// borrowed, or if the arena is not allowed to grow and there is no room left
     #[inline]
     pub fn allocate<T: 'static>(&mut self, v: T) -> Address<T> {
-        let self_ptr = self as *mut Arena;
-        let group = match self.data.get_mut::<LocationGroup<T>>() {
-            Some(v) => v,
-            None => {
-                // This hash value is used to keep track of what location groups have been freed.
-                // This is used to avoid use after free bugs when the arena is being de allocated.
-                // sometimes some of `Address` entities can cause segfault if they get dropped
-                // after their location group is dropped
-                let mut hasher = DefaultHasher::new();
-                let tid = TypeId::of::<T>();
-                tid.hash(&mut hasher);
-                let v = hasher.finish();
-                self.data
-                    .insert(LocationGroup::<T>::new(self.capacity, self_ptr, v));
-                self.data.get_mut::<LocationGroup<T>>().unwrap()
+        let group = self.group_or_insert::<T>();
+        // SAFETY: the arena is held mutably
+        unsafe { group.allocate(v) }
+    }
+
+    /// Adds a new entity to the arena and returns the address to that entity. If the arena is
+    /// not allowed to grow (see `ArenaConfig::grow`) and there is no room left for the type, the
+    /// entity is given back instead
+    ///
+    /// Panics if the entities of this type have to be moved to make room while some of them are
+    /// borrowed
+    pub fn try_allocate<T: 'static>(&mut self, v: T) -> Result<Address<T>, T> {
+        let group = self.group_or_insert::<T>();
+        // SAFETY: the arena is held mutably
+        unsafe { group.try_allocate(v) }
+    }
+
+    /// Adds every entity of an iterator to the arena and returns their addresses, in the same
+    /// order. Freed locations are filled first, then room is made for the rest at once, which is
+    /// faster than allocating them one by one
+    ///
+    /// Panics if the entities of this type have to be moved to make room while some of them are
+    /// borrowed, or if the arena is not allowed to grow and runs out of room
+    pub fn allocate_many<T: 'static, I: IntoIterator<Item = T>>(
+        &mut self,
+        values: I,
+    ) -> Vec<Address<T>> {
+        let group = self.group_or_insert::<T>();
+        // SAFETY: the arena is held mutably
+        unsafe { group.allocate_many(values) }
+    }
+
+    /// Adds `n` entities to the arena, made by calling `f` with the numbers from 0 to `n`, and
+    /// returns their addresses in the same order. See `allocate_many()`
+    pub fn allocate_from_fn<T: 'static>(
+        &mut self,
+        n: usize,
+        f: impl FnMut(usize) -> T,
+    ) -> Vec<Address<T>> {
+        self.allocate_many((0..n).map(f))
+    }
+
+    /// Mark the location of the address or handle as free. This opens up that location and all
+    /// remaining references will no longer be valid. The entity is dropped right away
+    ///
+    /// Panics if the entity is currently borrowed, or if the address belongs to another arena
+    #[inline]
+    pub fn free<T: 'static>(&self, address: impl Locator<T>) {
+        ArenaError::found(self.try_free(address));
+    }
+
+    /// Free the entity at a given address or handle, failing with the reason it could not be
+    /// freed instead of panicking
+    pub fn try_free<T: 'static>(&self, address: impl Locator<T>) -> Result<(), ArenaError> {
+        let (group, index, generation) = self.locate(&address)?;
+        group
+            .try_free(index, generation)
+            .map_err(|error| ArenaError::from_borrow(error, index, generation))
+    }
+
+    /// Makes room for at least `additional` more entities of a type, on top of the ones already
+    /// stored, so that allocating them does not grow the list of the type
+    ///
+    /// Panics if this moves entities while some of them are borrowed, or if the arena is not
+    /// allowed to grow and already holds entities of the type
+    pub fn reserve<T: 'static>(&mut self, additional: usize) {
+        let group = self.group_or_insert::<T>();
+        // SAFETY: the arena is held mutably
+        unsafe {
+            let locations = group.locations();
+            if locations.capacity() - locations.len() < additional {
+                group.before_moving();
+                group.locations_mut().reserve(additional);
             }
+        }
+    }
+
+    /// Drops the room the list of a type has beyond the entities it holds, freed locations
+    /// included. See `compact()` for getting rid of those
+    ///
+    /// Panics if this moves entities while some of them are borrowed, or if the arena is not
+    /// allowed to grow and holds entities of the type
+    pub fn shrink_to_fit<T: 'static>(&mut self) {
+        if let Some(group) = self.find_group::<T>() {
+            // SAFETY: the arena is held mutably
+            unsafe {
+                let locations = group.locations();
+                if locations.capacity() > locations.len() {
+                    group.before_moving();
+                    group.locations_mut().shrink_to_fit();
+                }
+            }
+        }
+    }
+
+    /// Moves every entity of a type to the front of its list and frees up the space of the
+    /// freed locations after them, so that iterating goes over entities stored next to each
+    /// other. Returns the table of where entities were moved, which is used for updating
+    /// their addresses. Addresses that are not updated stop resolving, as if the entity was freed
+    ///
+    /// Does nothing and returns an empty table if the arena is not allowed to grow, as its
+    /// entities never move
+    ///
+    /// Panics if any entity of the type is borrowed
+    pub fn compact<T: 'static>(&mut self) -> Remap<T> {
+        let moved = match self.find_group::<T>() {
+            // SAFETY: the arena is held mutably
+            Some(group) => unsafe { group.compact() },
+            None => HashMap::new(),
         };
-        let (generation, index): (usize, usize);
-        match group.free_indexes.get_mut().pop() {
-            Some(idx) => {
-                let location = &mut group.locations[idx];
-                generation = *location.generation.borrow();
-                index = idx;
-                location.entity = v;
+        Remap::new(moved)
+    }
+
+    /// Walks every live entity of a type like `iter_mut()` does, and frees the ones `keep`
+    /// returns false for. Returns the number of entities freed
+    ///
+    /// Panics when reaching an entity that is borrowed
+    pub fn retain<T: 'static>(
+        &mut self,
+        mut keep: impl FnMut(&Address<T>, &mut T) -> bool,
+    ) -> usize {
+        let mut removed = 0;
+        let mut entities = self.iter_mut::<T>();
+        while let Some((address, mut entity)) = entities.next() {
+            if !keep(&address, &mut entity) {
+                // the borrow has to end before the entity can be freed
+                drop(entity);
+                if let Some(group) = &entities.group {
+                    group.free(address.index, address.generation);
+                }
+                removed += 1;
             }
-            None => {
-                generation = 0;
-                index = group.locations.len();
-                group.locations.push(Location {
-                    entity: v,
-                    generation: RefCell::new(generation),
-                })
+        }
+        removed
+    }
+
+    /// Frees every entity of a type at once, without going through their addresses. Outstanding
+    /// addresses of the type stop resolving, and every location is ready to be reused
+    ///
+    /// Panics if any entity of the type is borrowed
+    pub fn clear<T: 'static>(&mut self) {
+        if let Some(group) = self.find_group::<T>() {
+            AnyGroup::clear(&**group)
+        }
+    }
+
+    /// Frees every entity of every type at once, see `clear()`
+    ///
+    /// Panics if any entity in the arena is borrowed, in which case nothing is freed
+    pub fn clear_all(&mut self) {
+        if self.groups.iter().any(|group| group.has_borrows()) {
+            panic!("can not clear the arena while its entities are borrowed");
+        }
+        for group in self.groups.iter() {
+            group.clear();
+        }
+    }
+
+    /// Drops the list of entities of a type along with every entity in it, as if no entity of
+    /// the type was ever allocated. Returns false if there was no such list. Entities borrowed at
+    /// the time are dropped once their last borrow ends
+    ///
+    /// Outstanding addresses of the type, owning or weak, stop resolving just like after dropping
+    /// the arena
+    pub fn remove_type<T: 'static>(&mut self) -> bool {
+        self.groups.remove::<T>().is_some()
+    }
+
+    /// Statistics of every type in the arena: how many entities are live and freed, how big the
+    /// lists are and roughly how much memory they take
+    pub fn stats(&self) -> ArenaStats {
+        ArenaStats {
+            types: self.groups.iter().map(|group| group.stats()).collect(),
+        }
+    }
+
+    /// Iterate over every live entity of a type, in the order they are stored in the arena.
+    /// Freed locations are skipped. Each item comes with an address to the entity, which counts
+    /// as a copy of the entity's other addresses
+    ///
+    /// Panics when reaching an entity that is mutably borrowed
+    pub fn iter<T: 'static>(&self) -> Iter<'_, T> {
+        Iter::new(self.find_group::<T>().cloned())
+    }
+
+    /// Iterate over every live entity of a type with mutable access, in the order they are stored
+    /// in the arena. Freed locations are skipped. Each item comes with an address to the entity,
+    /// which counts as a copy of the entity's other addresses
+    ///
+    /// Panics when reaching an entity that is borrowed
+    pub fn iter_mut<T: 'static>(&mut self) -> IterMut<'_, T> {
+        IterMut::new(self.find_group::<T>().cloned())
+    }
+}
+
+/// Checks that an address was allocated in the arena of the given id. Handles only carry the low
+/// bits of the id, which are checked instead
+fn check_arena<T>(expected: ArenaId, address: &impl Locator<T>) -> Result<(), ArenaError> {
+    match address.arena() {
+        Some(found) if found != expected => Err(ArenaError::ArenaMismatch { expected, found }),
+        Some(_) => Ok(()),
+        None => match address.arena_tag() {
+            Some(found) if found != expected.tag() => {
+                Err(ArenaError::HandleMismatch { expected, found })
             }
-        };
-        Address::<T> {
-            generation,
-            index,
-            phantom: PhantomData,
-            arena: self_ptr,
-            ref_count: Rc::new(RefCell::new(1)),
+            _ => Ok(()),
+        },
+    }
+}
+
+/// Finds the next live entity of a group starting from `next`, and gets an address to it
+fn next_address<T>(group: &LocationGroup<T>, next: &mut usize) -> Option<Address<T>> {
+    let locations = group.locations();
+    while *next < locations.len() {
+        let index = *next;
+        *next += 1;
+        let address = group.address(index, locations[index].generation.get());
+        if address.is_some() {
+            return address;
         }
     }
+    None
+}
 
-    /// Mark the location of the address as free. This opens up that location and all remaining
-    /// references will no longer be valid
-    #[inline]
-    pub fn free<T: 'static>(&self, address: &Address<T>) {
-        // this checks prevents dereferencing a location after it has been freed
-        let group = &self.data.get::<LocationGroup<T>>().unwrap();
-        if self.freed_groups.contains(&group.type_id_hash) {
-            return;
+impl<T> Iter<'_, T> {
+    pub(crate) fn new(group: Option<Rc<LocationGroup<T>>>) -> Self {
+        Iter {
+            group,
+            next: 0,
+            phantom: PhantomData,
         }
-        let location = &group.locations[address.index];
-        if *location.generation.borrow() == address.generation {
-            group.free_indexes.borrow_mut().push(address.index);
-            *location.generation.borrow_mut() += 1;
+    }
+}
+
+impl<T> IterMut<'_, T> {
+    pub(crate) fn new(group: Option<Rc<LocationGroup<T>>>) -> Self {
+        IterMut {
+            group,
+            next: 0,
+            phantom: PhantomData,
         }
     }
 }
 
+impl<'a, T: 'static> Iterator for Iter<'a, T> {
+    type Item = (Address<T>, Ref<'a, T>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let group = self.group.as_ref()?;
+        let address = next_address(group, &mut self.next)?;
+        let entity = found(Rc::clone(group).borrow(address.index, address.generation))?;
+        Some((address, entity))
+    }
+}
+
+impl<'a, T: 'static> Iterator for IterMut<'a, T> {
+    type Item = (Address<T>, RefMut<'a, T>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let group = self.group.as_ref()?;
+        let address = next_address(group, &mut self.next)?;
+        let entity = found(Rc::clone(group).borrow_mut(address.index, address.generation))?;
+        Some((address, entity))
+    }
+}
+
+impl<T> fmt::Debug for Iter<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Iter").field("next", &self.next).finish()
+    }
+}
+
+impl<T> fmt::Debug for IterMut<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("IterMut").field("next", &self.next).finish()
+    }
+}
+
+impl ArenaId {
+    /// A new id, never handed out before
+    pub(crate) fn next() -> ArenaId {
+        static NEXT: AtomicU64 = AtomicU64::new(0);
+        ArenaId(NEXT.fetch_add(1, Ordering::Relaxed))
+    }
+
+    /// The low 16 bits of the id, kept in handles
+    pub(crate) fn tag(self) -> u16 {
+        self.0 as u16
+    }
+}
+
+impl fmt::Display for ArenaId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "arena #{}", self.0)
+    }
+}
+
 impl Default for Arena {
     fn default() -> Self {
-        Arena::new(DEFAULT_CAPACITY)
+        Arena::with_config(ArenaConfig::default())
     }
 }
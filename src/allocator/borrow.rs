@@ -0,0 +1,142 @@
+/*!
+This module implements the guards handed out when borrowing entities from the arena.
+
+Every location in the arena keeps a borrow flag, the same way a `RefCell` does. Any number of
+`Ref` guards can be alive for an entity at once, or a single `RefMut` guard. Since copies of an
+address can live anywhere, conflicts are checked at runtime instead of by the compiler
+```rust
+use arena_allocator::{Address, Arena, BorrowError};
+let mut arena = Arena::default();
+
+#[derive(Debug)]
+struct Dog {name: String}
+
+let bruce = arena.allocate(Dog{name: format!("Bruce")});
+let other = bruce.copy();
+{
+    let mut dog = bruce.try_borrow_mut().unwrap();
+    dog.name.push_str(" the second");
+    assert_eq!(other.try_borrow().unwrap_err(), BorrowError::AlreadyBorrowed);
+    assert_eq!(other.try_borrow_mut().unwrap_err(), BorrowError::AlreadyBorrowed);
+}
+let first = bruce.try_borrow().unwrap();
+let second = other.try_borrow().unwrap();
+assert_eq!(first.name, second.name);
+assert_eq!(other.try_borrow_mut().unwrap_err(), BorrowError::AlreadyBorrowed);
+drop(first);
+drop(second);
+
+other.remove();
+assert_eq!(bruce.try_borrow().unwrap_err(), BorrowError::Freed);
+```
+ */
+
+#![forbid(missing_docs, missing_debug_implementations)]
+
+use std::cell;
+use std::error::Error;
+use std::fmt;
+use std::ops::{Deref, DerefMut};
+use std::rc::Rc;
+
+use super::arena::LocationGroup;
+
+/// Reasons borrowing an entity can fail
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BorrowError {
+    /// The entity the address was pointing to has been freed
+    Freed,
+    /// The entity is already borrowed in a way that conflicts with this borrow
+    AlreadyBorrowed,
+}
+
+impl fmt::Display for BorrowError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BorrowError::Freed => write!(f, "entity has been freed"),
+            BorrowError::AlreadyBorrowed => write!(f, "entity is already borrowed"),
+        }
+    }
+}
+
+impl Error for BorrowError {}
+
+/// Turns the result of a borrow into an option, where None means the entity was freed.
+/// Borrow conflicts are bugs in the caller and panic, just like `RefCell::borrow()` does
+pub(crate) fn found<R>(result: Result<R, BorrowError>) -> Option<R> {
+    match result {
+        Ok(v) => Some(v),
+        Err(BorrowError::Freed) => None,
+        Err(e) => panic!("{}", e),
+    }
+}
+
+/// Guard for a shared borrow of an entity in the arena. The entity can not be mutably borrowed
+/// or freed until the guard is dropped. The guard keeps the list of entities it borrows from
+/// alive, even if the arena is dropped in the meantime
+pub struct Ref<'a, T> {
+    // declared before the group, so the borrow ends before the group can be dropped
+    value: cell::Ref<'a, T>,
+    _group: Rc<LocationGroup<T>>,
+}
+
+/// Guard for a mutable borrow of an entity in the arena. The entity can not be borrowed again or
+/// freed until the guard is dropped. The guard keeps the list of entities it borrows from
+/// alive, even if the arena is dropped in the meantime
+pub struct RefMut<'a, T> {
+    // declared before the group, so the borrow ends before the group can be dropped
+    value: cell::RefMut<'a, T>,
+    _group: Rc<LocationGroup<T>>,
+}
+
+impl<'a, T> Ref<'a, T> {
+    pub(crate) fn new(value: cell::Ref<'a, T>, group: Rc<LocationGroup<T>>) -> Ref<'a, T> {
+        Ref {
+            value,
+            _group: group,
+        }
+    }
+}
+
+impl<'a, T> RefMut<'a, T> {
+    pub(crate) fn new(value: cell::RefMut<'a, T>, group: Rc<LocationGroup<T>>) -> RefMut<'a, T> {
+        RefMut {
+            value,
+            _group: group,
+        }
+    }
+}
+
+impl<T> Deref for Ref<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T> Deref for RefMut<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T> DerefMut for RefMut<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.value
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Ref<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.value.fmt(f)
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for RefMut<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.value.fmt(f)
+    }
+}
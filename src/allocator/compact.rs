@@ -0,0 +1,126 @@
+/*!
+This module implements the table returned by `Arena::compact()`, used for updating addresses of
+the entities that were moved.
+
+Freeing entities leaves holes in the list of their type, which are filled again in the reverse
+order they were freed. Compacting moves the entities at the end of the list into those holes and
+drops the vacant locations left at the end. Addresses are not tracked by the arena, so the ones
+pointing to moved entities have to be updated through the table
+```rust
+use arena_allocator::Arena;
+let mut arena = Arena::default();
+
+struct Dog {name: String}
+
+let mut dogs: Vec<_> = (0..4)
+    .map(|i| arena.allocate(Dog{name: format!("Dog #{}", i)}))
+    .collect();
+// frees the first two dogs, leaving holes at the front
+dogs.drain(..2);
+let stale = dogs[0].copy();
+
+let remap = arena.compact::<Dog>();
+assert_eq!(remap.len(), 2);
+for dog in dogs.iter_mut() {
+    assert!(remap.update(dog));
+}
+assert_eq!(arena.stats().of::<Dog>().unwrap().slots, 2);
+assert_eq!((dogs[0].index, dogs[1].index), (1, 0));
+assert_eq!(dogs[0].get().unwrap().name, "Dog #2");
+assert_eq!(dogs[1].get().unwrap().name, "Dog #3");
+
+// addresses that were not updated stop resolving, even once the list grows back
+assert!(stale.get().is_none());
+let new = arena.allocate(Dog{name: format!("Dog #4")});
+assert_eq!(new.index, 2);
+assert!(stale.get().is_none());
+```
+ */
+
+#![forbid(missing_docs, missing_debug_implementations)]
+
+use std::collections::HashMap;
+use std::fmt;
+use std::marker::PhantomData;
+
+use super::address::{Address, WeakAddress};
+
+/// Where the entities of a type were moved by `Arena::compact()`, by the index and generation
+/// they had before. Entities that were not moved are not in the table
+pub struct Remap<T: 'static> {
+    moved: HashMap<(usize, usize), Moved>,
+    phantom: PhantomData<Address<T>>,
+}
+
+/// The new location of a moved entity
+pub(crate) struct Moved {
+    pub(crate) index: usize,
+    pub(crate) generation: usize,
+}
+
+impl<T> Remap<T> {
+    pub(crate) fn new(moved: HashMap<(usize, usize), Moved>) -> Remap<T> {
+        Remap {
+            moved,
+            phantom: PhantomData,
+        }
+    }
+
+    /// The index and generation an entity was moved to, None if it was not moved
+    pub fn get(&self, index: usize, generation: usize) -> Option<(usize, usize)> {
+        self.moved
+            .get(&(index, generation))
+            .map(|moved| (moved.index, moved.generation))
+    }
+
+    /// Number of entities moved
+    pub fn len(&self) -> usize {
+        self.moved.len()
+    }
+
+    /// Whether no entity was moved
+    pub fn is_empty(&self) -> bool {
+        self.moved.is_empty()
+    }
+
+    /// Every move as `((old index, old generation), (new index, new generation))`, in no
+    /// particular order
+    pub fn iter(&self) -> impl Iterator<Item = ((usize, usize), (usize, usize))> + '_ {
+        self.moved
+            .iter()
+            .map(|(&old, moved)| (old, (moved.index, moved.generation)))
+    }
+
+    /// Points an address to where its entity was moved. Returns false and leaves the address
+    /// as is if the entity was not moved
+    pub fn update(&self, address: &mut Address<T>) -> bool {
+        match self.get(address.index, address.generation) {
+            Some((index, generation)) => {
+                // the address keeps its share of the count, which moved along with the entity
+                address.index = index;
+                address.generation = generation;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Points a weak address to where its entity was moved. Returns false and leaves the address
+    /// as is if the entity was not moved
+    pub fn update_weak(&self, address: &mut WeakAddress<T>) -> bool {
+        match self.get(address.index, address.generation) {
+            Some((index, generation)) => {
+                address.index = index;
+                address.generation = generation;
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+impl<T> fmt::Debug for Remap<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
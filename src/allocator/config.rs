@@ -0,0 +1,217 @@
+/*!
+This module implements the configuration an arena is created with.
+
+Every time a location is freed its generation goes up by one, so that addresses to the freed
+entity stop resolving. Generations are limited to `generation_bits` bits, so they can not wrap
+around and make old addresses valid again. A location whose generation reached the limit is
+retired once its entity is freed, and is never reused
+```rust
+use arena_allocator::{Arena, ArenaConfig};
+let mut arena = Arena::with_config(ArenaConfig {
+    generation_bits: 2,
+    ..ArenaConfig::default()
+});
+
+struct Dog;
+
+// generations 0 to 3 fit in 2 bits, the first location is used 4 times
+let mut stale = Vec::new();
+for generation in 0..4 {
+    let dog = arena.allocate(Dog);
+    assert_eq!((dog.index, dog.generation), (0, generation));
+    stale.push(dog.downgrade());
+}
+// and then retired, instead of going back to generation 0
+let dog = arena.allocate(Dog);
+assert_eq!((dog.index, dog.generation), (1, 0));
+assert!(stale.iter().all(|address| address.get().is_none()));
+assert_eq!(arena.stats().of::<Dog>().unwrap().retired, 1);
+
+// compacting does not move entities into retired locations
+assert!(arena.compact::<Dog>().is_empty());
+assert_eq!(arena.allocate(Dog).index, 2);
+```
+An arena that is not allowed to `grow` keeps every type within its capacity. Its lists are never
+reallocated and compacting leaves them as they are, so entities stay at the same place in memory
+for as long as the arena lives
+```rust
+use arena_allocator::{Address, Arena, ArenaConfig};
+let mut arena = Arena::with_config(ArenaConfig::fixed(2));
+
+struct Dog {name: String}
+
+let place = |dog: &Address<Dog>| &*dog.get().unwrap() as *const Dog;
+let bruce = arena.try_allocate(Dog{name: format!("Bruce")}).ok().unwrap();
+let bruce_place = place(&bruce);
+let rex = arena.try_allocate(Dog{name: format!("Rex")}).ok().unwrap();
+let max = arena.try_allocate(Dog{name: format!("Max")}).err().unwrap();
+assert_eq!(max.name, "Max");
+
+// freed locations can still be reused
+drop(rex);
+let max = arena.try_allocate(max).ok().unwrap();
+assert_eq!(max.index, 1);
+assert_eq!(place(&bruce), bruce_place);
+
+// compacting would move max in front of bruce
+let max_place = place(&max);
+drop(bruce);
+assert!(arena.compact::<Dog>().is_empty());
+assert_eq!(place(&max), max_place);
+```
+Each type can be given a capacity of its own through the builder, or by reserving room for it
+later on
+```rust
+use arena_allocator::{Arena, ArenaConfig};
+
+struct Particle;
+struct Player;
+struct Monster;
+
+let mut arena = Arena::builder()
+    .config(ArenaConfig::fixed(8))
+    .with_type_capacity::<Particle>(50_000)
+    .with_type_capacity::<Player>(4)
+    .build();
+arena.allocate(Particle);
+arena.allocate(Player);
+arena.allocate(Monster);
+let stats = arena.stats();
+assert_eq!(stats.of::<Particle>().unwrap().capacity, 50_000);
+assert_eq!(stats.of::<Player>().unwrap().capacity, 4);
+assert_eq!(stats.of::<Monster>().unwrap().capacity, 8);
+
+let mut arena = Arena::default();
+struct Dog {name: String}
+
+arena.reserve::<Dog>(100);
+let bruce = arena.allocate(Dog{name: format!("Bruce")});
+assert!(arena.stats().of::<Dog>().unwrap().capacity >= 100);
+arena.shrink_to_fit::<Dog>();
+assert_eq!(arena.stats().of::<Dog>().unwrap().capacity, 1);
+assert_eq!(bruce.get().unwrap().name, "Bruce");
+```
+ */
+
+#![forbid(missing_docs, missing_debug_implementations)]
+
+use std::any::TypeId;
+use std::collections::HashMap;
+
+use super::arena::Arena;
+use super::storage::Storage;
+
+static DEFAULT_CAPACITY: usize = 16;
+static DEFAULT_GENERATION_BITS: u32 = 24;
+
+/// Settings of an arena, see `Arena::with_config()`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct ArenaConfig {
+    /// Initial size of all arrays created for each entity
+    pub capacity: usize,
+    /// Number of bits generations can use, from 1 up to the bits of a `usize`. Defaults to 24,
+    /// so that generations behave the same on 32 and 64 bit targets and always fit in a `Handle`
+    pub generation_bits: u32,
+    /// Whether the list of a type can grow past its capacity. When it can not, allocating fails
+    /// once the list is full, and entities never move in memory. Defaults to true
+    pub grow: bool,
+    /// How the locations of each type are laid out in memory. Defaults to a contiguous list
+    pub storage: Storage,
+}
+
+impl ArenaConfig {
+    /// Settings of an arena that holds at most `capacity` entities of each type, and never moves
+    /// them
+    pub fn fixed(capacity: usize) -> ArenaConfig {
+        ArenaConfig {
+            capacity,
+            grow: false,
+            ..ArenaConfig::default()
+        }
+    }
+
+    /// Checks that the settings are valid
+    ///
+    /// Panics if `generation_bits` is 0 or more than the bits of a `usize`, or if the storage is
+    /// paged with pages of 0 locations
+    pub(crate) fn check(&self) {
+        if let Err(error) = self.validate() {
+            panic!("{}", error);
+        }
+    }
+
+    /// Same as `check()`, failing instead of panicking
+    pub(crate) fn validate(&self) -> Result<(), String> {
+        if self.generation_bits == 0 || self.generation_bits > usize::BITS {
+            return Err(format!(
+                "generation_bits must be between 1 and {}",
+                usize::BITS
+            ));
+        }
+        if let Storage::Paged { page_size: 0 } = self.storage {
+            return Err("page_size must be at least 1".to_string());
+        }
+        Ok(())
+    }
+
+    /// The highest generation a location can reach
+    pub(crate) fn max_generation(&self) -> usize {
+        if self.generation_bits >= usize::BITS {
+            usize::MAX
+        } else {
+            (1 << self.generation_bits) - 1
+        }
+    }
+}
+
+/// Builds an arena, created by `Arena::builder()`
+#[derive(Debug)]
+pub struct ArenaBuilder {
+    config: ArenaConfig,
+    type_capacities: HashMap<TypeId, usize>,
+}
+
+impl ArenaBuilder {
+    pub(crate) fn new() -> ArenaBuilder {
+        ArenaBuilder {
+            config: ArenaConfig::default(),
+            type_capacities: HashMap::new(),
+        }
+    }
+
+    /// Settings of the arena. The capacity in them is used by every type that is not given one
+    /// of its own
+    pub fn config(mut self, config: ArenaConfig) -> ArenaBuilder {
+        self.config = config;
+        self
+    }
+
+    /// Capacity of the list of type `T`, instead of the one in the settings of the arena. When
+    /// the arena is not allowed to grow, this is the most entities of the type it can hold
+    pub fn with_type_capacity<T: 'static>(mut self, capacity: usize) -> ArenaBuilder {
+        self.type_capacities.insert(TypeId::of::<T>(), capacity);
+        self
+    }
+
+    /// Creates the arena
+    ///
+    /// Panics if the settings are not valid, see `Arena::with_config()`
+    pub fn build(self) -> Arena {
+        let mut arena = Arena::with_config(self.config);
+        arena.type_capacities = self.type_capacities;
+        arena
+    }
+}
+
+impl Default for ArenaConfig {
+    fn default() -> Self {
+        ArenaConfig {
+            capacity: DEFAULT_CAPACITY,
+            generation_bits: DEFAULT_GENERATION_BITS,
+            grow: true,
+            storage: Storage::Contiguous,
+        }
+    }
+}
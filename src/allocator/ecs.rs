@@ -0,0 +1,437 @@
+/*!
+This module implements a small entity component system on top of the arena.
+
+An entity is nothing but an id, handed out by `World::spawn()`. Components of any type are attached
+to it, at most one of each type, and kept in the arena with the other components of that type.
+Entity ids are generational just like addresses, so an id of a despawned entity never resolves to
+an entity spawned later, and despawning an entity frees all of its components at once
+```rust
+use arena_allocator::{Entity, World};
+
+struct Name(String);
+struct Health(i8);
+struct Target(Entity);
+
+let mut world = World::new();
+let human = world.spawn();
+world.attach(human, Name(format!("Nader")));
+world.attach(human, Health(100));
+let monster = world.spawn();
+world.attach(monster, Name(format!("Borrow checker")));
+world.attach(monster, Health(50));
+world.attach(monster, Target(human));
+
+// the monster hits its target
+let target = world.get::<Target>(monster).unwrap().0;
+world.get_mut::<Health>(target).unwrap().0 -= 5;
+assert_eq!(world.get::<Health>(human).unwrap().0, 95);
+assert_eq!(world.query::<Health>().count(), 2);
+let targeting: Vec<_> = world.query::<Target>().map(|(entity, _)| entity).collect();
+assert_eq!(targeting, vec![monster]);
+
+// attaching a component of a type the entity already has replaces it
+assert_eq!(world.attach(monster, Health(10)).map(|health| health.0), Some(50));
+assert_eq!(world.detach::<Health>(monster).map(|health| health.0), Some(10));
+assert!(!world.has::<Health>(monster));
+```
+Components can also be reached through regular addresses, which stop resolving once the entity is
+despawned. An entity can not be despawned while one of its components is borrowed
+```rust
+use arena_allocator::World;
+use std::panic::{self, AssertUnwindSafe};
+
+struct Name(String);
+struct Health(i8);
+
+let mut world = World::new();
+let human = world.spawn();
+world.attach(human, Health(100));
+world.attach(human, Name(format!("Nader")));
+let name = world.address::<Name>(human).unwrap();
+let borrowed = name.get().unwrap();
+assert!(panic::catch_unwind(AssertUnwindSafe(|| world.despawn(human))).is_err());
+drop(borrowed);
+assert!(world.has::<Health>(human));
+
+assert!(world.despawn(human));
+assert!(!world.is_alive(human));
+assert!(name.get().is_none());
+assert!(world.get::<Name>(human).is_none());
+assert!(!world.despawn(human));
+
+// the location of the entity is reused, under another generation
+let again = world.spawn();
+assert_eq!(again.index(), human.index());
+assert_ne!(again, human);
+assert!(!world.has::<Name>(again));
+```
+ */
+
+#![forbid(missing_docs, missing_debug_implementations)]
+
+use std::any::TypeId;
+use std::collections::HashMap;
+use std::fmt;
+use std::marker::PhantomData;
+use std::mem;
+use std::rc::Rc;
+
+use super::address::Address;
+use super::arena::{Arena, ArenaId};
+use super::borrow::{found, BorrowError, Ref, RefMut};
+use super::config::ArenaConfig;
+use super::error::ArenaError;
+use super::handle::Locator;
+use super::stats::ArenaStats;
+
+/// A world of entities and their components
+#[derive(Debug)]
+pub struct World {
+    /// Holds the entities as well as their components, each type in a group of its own
+    arena: Arena,
+    /// The owner of every component, by the type and index of the component, so that the
+    /// components of a type can be walked without going through every entity
+    owners: HashMap<TypeId, Vec<Option<Owner>>>,
+}
+
+/// Id of an entity of a world, created by `World::spawn()`. Ids are compared by the location and
+/// generation of their entity. Unlike a `Handle`, an id keeps its index and generation whole, so
+/// it is not limited to 24 bits of each
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Entity {
+    index: usize,
+    generation: usize,
+    world: ArenaId,
+}
+
+/// Location of an entity or component in the arena of a world, for looking it up through the
+/// arena with the arena checked
+struct Key<T> {
+    index: usize,
+    generation: usize,
+    arena: ArenaId,
+    phantom: PhantomData<fn() -> T>,
+}
+
+/// What the arena holds for every entity, the location of each of its components
+pub(crate) struct EntityData {
+    components: Vec<Component>,
+}
+
+/// Location of a component in the group of its type
+struct Component {
+    type_id: TypeId,
+    index: usize,
+    generation: usize,
+}
+
+/// The entity a component belongs to. Only holds for the component of the given generation, the
+/// location may have been freed or reused since
+#[derive(Debug)]
+struct Owner {
+    generation: usize,
+    entity: Entity,
+}
+
+impl World {
+    /// Creates an empty world
+    pub fn new() -> World {
+        World::with_config(ArenaConfig::default())
+    }
+
+    /// Creates an empty world, with the given settings for the arena holding every type of
+    /// component. See `Arena::with_config()`
+    pub fn with_config(config: ArenaConfig) -> World {
+        World {
+            arena: Arena::with_config(config),
+            owners: HashMap::new(),
+        }
+    }
+
+    /// Creates a new entity without any component
+    ///
+    /// Panics if the arena is not allowed to grow and there is no room left
+    pub fn spawn(&mut self) -> Entity {
+        let group = self.arena.group_or_insert::<EntityData>();
+        let data = EntityData {
+            components: Vec::new(),
+        };
+        // the entity is owned by the world rather than by an address
+        // SAFETY: the arena is held mutably
+        let (index, generation) = match unsafe { group.try_insert(data) } {
+            Ok(location) => location,
+            Err(_) => panic!("the world is full and not allowed to grow"),
+        };
+        Entity {
+            index,
+            generation,
+            world: self.arena.id(),
+        }
+    }
+
+    /// Frees an entity along with all of its components. Returns false if the entity was already
+    /// despawned
+    ///
+    /// Panics if the entity or any of its components is borrowed, or if the entity belongs to
+    /// another world
+    pub fn despawn(&mut self, entity: Entity) -> bool {
+        // every borrow is checked before anything is freed, so a panic leaves the entity whole
+        let borrowed = match self.data(entity) {
+            Some(data) => data.components.iter().any(|component| {
+                self.arena
+                    .find_any_group(component.type_id)
+                    .is_some_and(|group| group.has_borrow(component.index, component.generation))
+            }),
+            None => return false,
+        };
+        if borrowed {
+            panic!("can not despawn an entity while its components are borrowed");
+        }
+        let group = match self.arena.find_group::<EntityData>() {
+            Some(group) => group,
+            None => return false,
+        };
+        let data = match group.take(entity.index(), entity.generation()) {
+            Ok(data) => data,
+            Err(BorrowError::Freed) => return false,
+            Err(BorrowError::AlreadyBorrowed) => {
+                panic!("can not despawn an entity while it is borrowed")
+            }
+        };
+        for component in data.components {
+            if let Some(group) = self.arena.find_any_group(component.type_id) {
+                group.free(component.index, component.generation);
+            }
+        }
+        true
+    }
+
+    /// Whether the entity was not despawned yet
+    ///
+    /// Panics if the entity belongs to another world
+    pub fn is_alive(&self, entity: Entity) -> bool {
+        self.data(entity).is_some()
+    }
+
+    /// Every entity of the world, in the order they are stored
+    pub fn entities(&self) -> impl Iterator<Item = Entity> + '_ {
+        let world = self.arena.id();
+        self.arena
+            .iter::<EntityData>()
+            .map(move |(address, _)| Entity {
+                index: address.index,
+                generation: address.generation,
+                world,
+            })
+    }
+
+    /// Attaches a component to an entity, and returns the component of the same type it replaced
+    /// if the entity had one
+    ///
+    /// Panics if the entity was despawned or belongs to another world, if the component it
+    /// replaces is borrowed, or if the arena is not allowed to grow and there is no room left
+    pub fn attach<C: 'static>(&mut self, entity: Entity, component: C) -> Option<C> {
+        let attached = match self.data(entity) {
+            Some(data) => data.find::<C>(self.arena.id()),
+            None => panic!("can not attach a component to a despawned entity"),
+        };
+        if let Some(key) = attached {
+            if let Some(mut current) = self.arena.get_mut(key) {
+                return Some(mem::replace(&mut *current, component));
+            }
+            // the component was removed through one of its addresses, the new one takes its place
+            self.forget::<C>(entity);
+        }
+        let group = self.arena.group_or_insert::<C>();
+        // SAFETY: the arena is held mutably
+        let (index, generation) = match unsafe { group.try_insert(component) } {
+            Ok(location) => location,
+            Err(_) => panic!("the world is full and not allowed to grow"),
+        };
+        if let Some(mut data) = self.data_mut(entity) {
+            data.components.push(Component {
+                type_id: TypeId::of::<C>(),
+                index,
+                generation,
+            });
+        }
+        let owners = self.owners.entry(TypeId::of::<C>()).or_default();
+        if owners.len() <= index {
+            owners.resize_with(index + 1, || None);
+        }
+        owners[index] = Some(Owner { generation, entity });
+        None
+    }
+
+    /// Detaches the component of a type from an entity, and gives it back. None means the entity
+    /// has no such component, or was despawned
+    ///
+    /// Panics if the component is borrowed, or if the entity belongs to another world
+    pub fn detach<C: 'static>(&mut self, entity: Entity) -> Option<C> {
+        let key = self.data(entity)?.find::<C>(self.arena.id())?;
+        let group = self.arena.find_group::<C>()?;
+        let detached = match group.take(key.index, key.generation) {
+            Ok(component) => Some(component),
+            Err(BorrowError::Freed) => None,
+            Err(BorrowError::AlreadyBorrowed) => {
+                panic!("can not detach a component while it is borrowed")
+            }
+        };
+        self.forget::<C>(entity);
+        detached
+    }
+
+    /// Whether an entity has a component of a type
+    ///
+    /// Panics if the entity belongs to another world
+    pub fn has<C: 'static>(&self, entity: Entity) -> bool {
+        self.get::<C>(entity).is_some()
+    }
+
+    /// Borrow the component of a type of an entity. None means the entity has no such component,
+    /// or was despawned
+    ///
+    /// Panics if the component is mutably borrowed, or if the entity belongs to another world
+    pub fn get<C: 'static>(&self, entity: Entity) -> Option<Ref<'_, C>> {
+        let key = self.data(entity)?.find::<C>(self.arena.id())?;
+        self.arena.get(key)
+    }
+
+    /// Mutably borrow the component of a type of an entity. None means the entity has no such
+    /// component, or was despawned
+    ///
+    /// Panics if the component is borrowed, or if the entity belongs to another world
+    pub fn get_mut<C: 'static>(&mut self, entity: Entity) -> Option<RefMut<'_, C>> {
+        let key = self.data(entity)?.find::<C>(self.arena.id())?;
+        self.arena.get_mut(key)
+    }
+
+    /// Get a new address to the component of a type of an entity, which counts as another owner
+    /// of it. Despawning the entity or detaching the component frees it all the same
+    ///
+    /// Panics if the entity belongs to another world
+    pub fn address<C: 'static>(&self, entity: Entity) -> Option<Address<C>> {
+        let key = self.data(entity)?.find::<C>(self.arena.id())?;
+        self.arena
+            .find_group::<C>()?
+            .address(key.index, key.generation)
+    }
+
+    /// Every entity with a component of a type, along with a borrow of that component. Walks
+    /// the components of the type only, in the order they are stored
+    ///
+    /// Panics when reaching a component that is mutably borrowed
+    pub fn query<C: 'static>(&self) -> impl Iterator<Item = (Entity, Ref<'_, C>)> + '_ {
+        let group = self.arena.find_group::<C>();
+        let owners = match self.owners.get(&TypeId::of::<C>()) {
+            Some(owners) => owners.as_slice(),
+            None => &[],
+        };
+        owners.iter().enumerate().filter_map(move |(index, owner)| {
+            let owner = owner.as_ref()?;
+            let component = found(Rc::clone(group?).borrow(index, owner.generation))?;
+            Some((owner.entity, component))
+        })
+    }
+
+    /// Statistics of the entities and of every type of component, see `Arena::stats()`
+    pub fn stats(&self) -> ArenaStats {
+        self.arena.stats()
+    }
+
+    /// The data of an entity, None if it was despawned
+    ///
+    /// Panics if the entity belongs to another world
+    fn data(&self, entity: Entity) -> Option<Ref<'_, EntityData>> {
+        self.check(entity);
+        self.arena.get(entity.key())
+    }
+
+    /// The data of an entity mutably, None if it was despawned
+    fn data_mut(&mut self, entity: Entity) -> Option<RefMut<'_, EntityData>> {
+        self.check(entity);
+        self.arena.get_mut(entity.key())
+    }
+
+    /// Drops the location of the component of a type from the data of an entity
+    fn forget<C: 'static>(&mut self, entity: Entity) {
+        if let Some(mut data) = self.data_mut(entity) {
+            data.components
+                .retain(|component| component.type_id != TypeId::of::<C>());
+        }
+    }
+
+    /// Panics if the entity belongs to another world
+    fn check(&self, entity: Entity) {
+        if entity.world != self.arena.id() {
+            let error = ArenaError::ArenaMismatch {
+                expected: self.arena.id(),
+                found: entity.world,
+            };
+            panic!("{}", error);
+        }
+    }
+}
+
+impl Entity {
+    /// Index of the entity in the world
+    pub fn index(self) -> usize {
+        self.index
+    }
+
+    /// Generation of the entity
+    pub fn generation(self) -> usize {
+        self.generation
+    }
+
+    /// Where the data of the entity is kept in the arena of its world
+    fn key(self) -> Key<EntityData> {
+        Key::new(self.index, self.generation, self.world)
+    }
+}
+
+impl EntityData {
+    /// Where the component of a type is kept, if the entity has one
+    fn find<C: 'static>(&self, arena: ArenaId) -> Option<Key<C>> {
+        self.components
+            .iter()
+            .find(|component| component.type_id == TypeId::of::<C>())
+            .map(|component| Key::new(component.index, component.generation, arena))
+    }
+}
+
+impl<T> Key<T> {
+    fn new(index: usize, generation: usize, arena: ArenaId) -> Key<T> {
+        Key {
+            index,
+            generation,
+            arena,
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<T> Locator<T> for Key<T> {
+    fn locate(&self) -> (usize, usize) {
+        (self.index, self.generation)
+    }
+
+    fn arena(&self) -> Option<ArenaId> {
+        Some(self.arena)
+    }
+}
+
+impl Default for World {
+    fn default() -> Self {
+        World::new()
+    }
+}
+
+impl fmt::Debug for Entity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Entity")
+            .field("index", &self.index())
+            .field("generation", &self.generation())
+            .finish()
+    }
+}
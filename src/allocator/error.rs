@@ -0,0 +1,173 @@
+/*!
+This module implements the errors returned by the arena.
+
+The `try_` methods of the arena never panic, and tell why an address could not be used instead
+```rust
+use arena_allocator::{Arena, ArenaError, Handle};
+let mut arena = Arena::default();
+
+#[derive(Debug)]
+struct Dog {name: String}
+#[derive(Debug)]
+struct Cat {name: String}
+
+let bruce = arena.allocate(Dog{name: format!("Bruce")});
+let handle = bruce.handle();
+assert_eq!(arena.try_get(handle).unwrap().name, "Bruce");
+
+let guard = arena.try_get(&bruce).unwrap();
+assert_eq!(arena.try_free(&bruce), Err(ArenaError::AlreadyBorrowed {index: 0}));
+drop(guard);
+arena.try_free(&bruce).unwrap();
+assert_eq!(arena.try_free(&bruce), Err(ArenaError::Stale {index: 0, generation: 0}));
+assert_eq!(arena.try_get(handle).unwrap_err(), ArenaError::Stale {index: 0, generation: 0});
+
+// a forged handle
+let forged = Handle::<Dog>::new(7, 0, arena.id());
+assert_eq!(arena.try_get(forged).unwrap_err(), ArenaError::IndexOutOfRange {index: 7, len: 1});
+assert!(arena.get(forged).is_none());
+let cat = Handle::<Cat>::new(0, 0, arena.id());
+assert!(matches!(arena.try_get(cat).unwrap_err(), ArenaError::UnknownType {..}));
+```
+Every arena has an id of its own, carried by every address allocated in it. An address handed to
+another arena is caught instead of silently pointing to whatever entity sits at its index there
+```rust
+use arena_allocator::{Arena, ArenaError};
+let mut live = Arena::default();
+let mut scratch = Arena::default();
+
+struct Dog {name: String}
+
+let bruce = live.allocate(Dog{name: format!("Bruce")});
+let rex = scratch.allocate(Dog{name: format!("Rex")});
+assert_eq!(bruce.arena, live.id());
+assert_ne!(live.id(), scratch.id());
+
+match scratch.try_get(&bruce) {
+    Err(ArenaError::ArenaMismatch {expected, found}) => {
+        assert_eq!(expected, scratch.id());
+        assert_eq!(found, live.id());
+    }
+    _ => panic!("bruce does not live in the scratch arena"),
+}
+assert!(scratch.try_free(&bruce.downgrade()).is_err());
+assert!(scratch.try_get_mut(&bruce).is_err());
+assert_eq!(scratch.try_get(&rex).unwrap().name, "Rex");
+assert_eq!(bruce.get().unwrap().name, "Bruce");
+```
+The methods that do not return a `Result` panic instead
+```rust,should_panic
+use arena_allocator::Arena;
+let mut live = Arena::default();
+let scratch = Arena::default();
+
+let bruce = live.allocate(format!("Bruce"));
+scratch.get(&bruce);
+```
+ */
+
+#![forbid(missing_docs, missing_debug_implementations)]
+
+use std::error::Error;
+use std::fmt;
+
+use super::arena::ArenaId;
+use super::borrow::BorrowError;
+
+/// Reasons an arena can refuse an address
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ArenaError {
+    /// No entity of the type was ever allocated in the arena
+    UnknownType {
+        /// Name of the type, as given by `std::any::type_name()`
+        type_name: &'static str,
+    },
+    /// The index is past the end of the list of entities of the type
+    IndexOutOfRange {
+        /// Index of the address
+        index: usize,
+        /// Number of locations of the type
+        len: usize,
+    },
+    /// The entity the address was pointing to has been freed
+    Stale {
+        /// Index of the address
+        index: usize,
+        /// Generation of the address
+        generation: usize,
+    },
+    /// The address was allocated in another arena
+    ArenaMismatch {
+        /// Id of the arena the address was handed to
+        expected: ArenaId,
+        /// Id of the arena the address was allocated in
+        found: ArenaId,
+    },
+    /// The handle was made in another arena. Handles only keep the low bits of the id of their
+    /// arena, see `Handle`
+    HandleMismatch {
+        /// Id of the arena the handle was handed to
+        expected: ArenaId,
+        /// Low bits of the id of the arena the handle was made in
+        found: u16,
+    },
+    /// The entity is already borrowed in a way that conflicts with this borrow
+    AlreadyBorrowed {
+        /// Index of the address
+        index: usize,
+    },
+}
+
+impl ArenaError {
+    /// The error for a failed borrow of the entity at an index
+    pub(crate) fn from_borrow(error: BorrowError, index: usize, generation: usize) -> ArenaError {
+        match error {
+            BorrowError::Freed => ArenaError::Stale { index, generation },
+            BorrowError::AlreadyBorrowed => ArenaError::AlreadyBorrowed { index },
+        }
+    }
+
+    /// Turns the result of a lookup into an option, where None means there is no such entity.
+    /// Mismatched arenas and borrow conflicts are bugs in the caller and panic, see
+    /// `borrow::found()`
+    pub(crate) fn found<R>(result: Result<R, ArenaError>) -> Option<R> {
+        match result {
+            Ok(v) => Some(v),
+            Err(ArenaError::UnknownType { .. })
+            | Err(ArenaError::IndexOutOfRange { .. })
+            | Err(ArenaError::Stale { .. }) => None,
+            Err(e) => panic!("{}", e),
+        }
+    }
+}
+
+impl fmt::Display for ArenaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ArenaError::UnknownType { type_name } => {
+                write!(f, "no entity of type {} was allocated", type_name)
+            }
+            ArenaError::IndexOutOfRange { index, len } => {
+                write!(f, "index {} is out of range of {} locations", index, len)
+            }
+            ArenaError::Stale { index, generation } => write!(
+                f,
+                "entity at index {} of generation {} has been freed",
+                index, generation
+            ),
+            ArenaError::ArenaMismatch { expected, found } => {
+                write!(f, "address of {} can not be used with {}", found, expected)
+            }
+            ArenaError::HandleMismatch { expected, found } => write!(
+                f,
+                "handle of an arena tagged {} can not be used with {}",
+                found, expected
+            ),
+            ArenaError::AlreadyBorrowed { index } => {
+                write!(f, "entity at index {} is already borrowed", index)
+            }
+        }
+    }
+}
+
+impl Error for ArenaError {}
@@ -0,0 +1,288 @@
+/*!
+This module implements handles, a compact alternative to addresses.
+
+A `Handle` is the index and generation of an entity packed in a single `u64`, along with the low
+bits of the id of its arena. It does not own the entity, so it is resolved through the arena
+instead of on its own. That makes it cheap to store in large numbers, to compare, sort and hash
+```rust
+use arena_allocator::{Arena, Handle};
+use std::collections::HashSet;
+let mut arena = Arena::default();
+
+struct Dog {name: String}
+
+let bruce = arena.allocate(Dog{name: format!("Bruce")});
+let handle = bruce.handle();
+assert_eq!(std::mem::size_of::<Handle<Dog>>(), 8);
+assert_eq!(arena.get(handle).unwrap().name, "Bruce");
+arena.get_mut(handle).unwrap().name.push_str(" the second");
+
+// an address can be made from a handle for as long as the entity is alive
+let again = arena.address(handle).unwrap();
+drop(bruce);
+assert_eq!(again.get().unwrap().name, "Bruce the second");
+drop(again);
+assert!(arena.get(handle).is_none());
+assert!(arena.address(handle).is_none());
+
+let rex = arena.allocate(Dog{name: format!("Rex")});
+let handles: HashSet<_> = vec![handle, rex.handle(), Handle::from(&rex)].into_iter().collect();
+assert_eq!(handles.len(), 2);
+// rex reuses the location of bruce, with a later generation
+assert!(handle < rex.handle());
+```
+Handles have room for 24 bits of index and 24 bits of generation. Addresses past that, which
+only happen with more than 2^24 entities of a type or with `ArenaConfig::generation_bits` set above
+24, have no handle: `try_handle()` gives None for them where `handle()` panics
+```rust
+use arena_allocator::{Arena, Handle};
+let mut arena = Arena::default();
+
+let bruce = arena.allocate(format!("Bruce"));
+assert_eq!(bruce.try_handle(), Some(bruce.handle()));
+assert!(Handle::<String>::try_new(1 << 24, 0, arena.id()).is_none());
+```
+An arena refuses handles of other arenas, just like it refuses their addresses. Only 16 bits of
+the id of the arena fit in a handle, so two arenas created 65536 arenas apart can not be told apart
+```rust
+use arena_allocator::{Arena, ArenaError};
+let mut live = Arena::default();
+let mut scratch = Arena::default();
+
+let bruce = live.allocate(format!("Bruce"));
+let draft = scratch.allocate(format!("Draft"));
+assert!(matches!(scratch.try_get(bruce.handle()), Err(ArenaError::HandleMismatch { .. })));
+// even though draft sits at the same index and generation in the scratch arena
+assert!(scratch.address(bruce.handle()).is_none());
+assert_eq!(*scratch.address(draft.handle()).unwrap().get().unwrap(), "Draft");
+```
+ */
+
+#![forbid(missing_docs, missing_debug_implementations)]
+
+use std::cmp::Ordering;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
+
+use super::address::{Address, WeakAddress};
+use super::arena::ArenaId;
+
+/// Index and generation of an entity packed in a `u64`. From the high bits to the low ones, 16 bits
+/// of the id of the arena, 24 bits of index and 24 bits of generation. Handles of the same arena
+/// order by index first
+pub struct Handle<T: 'static> {
+    bits: u64,
+    phantom: PhantomData<fn() -> T>,
+}
+
+/// Anything that points to an entity of type `T` in an arena, by its index and generation
+pub trait Locator<T> {
+    /// Index and generation of the entity
+    fn locate(&self) -> (usize, usize);
+
+    /// Id of the arena the entity lives in, if known
+    fn arena(&self) -> Option<ArenaId> {
+        None
+    }
+
+    /// Low bits of the id of the arena the entity lives in, for locators that do not have room
+    /// for the whole id. Only looked at when `arena()` is None
+    fn arena_tag(&self) -> Option<u16> {
+        self.arena().map(ArenaId::tag)
+    }
+}
+
+/// Bits of a handle used by the index and by the generation, each
+const INDEX_BITS: u32 = 24;
+
+/// Largest index or generation that fits in a handle
+const MAX_INDEX: usize = (1 << INDEX_BITS) - 1;
+
+impl<T> Handle<T> {
+    /// Creates a handle from an index and a generation of an entity in a given arena
+    ///
+    /// Panics if either does not fit in 24 bits, which can only happen with more than 2^24
+    /// entities of a type, or with a location reused more than 2^24 times while `generation_bits`
+    /// is set above 24
+    pub fn new(index: usize, generation: usize, arena: ArenaId) -> Handle<T> {
+        match Handle::try_new(index, generation, arena) {
+            Some(handle) => handle,
+            None => panic!(
+                "index and generation must fit in {} bits to make a handle",
+                INDEX_BITS
+            ),
+        }
+    }
+
+    /// Same as `new()`, giving None instead of panicking if the index or generation does not fit
+    pub fn try_new(index: usize, generation: usize, arena: ArenaId) -> Option<Handle<T>> {
+        if index > MAX_INDEX || generation > MAX_INDEX {
+            return None;
+        }
+        let bits = (u64::from(arena.tag()) << (2 * INDEX_BITS))
+            | ((index as u64) << INDEX_BITS)
+            | generation as u64;
+        Some(Handle::from_bits(bits))
+    }
+
+    /// Creates a handle from the bits of `to_bits()`
+    pub fn from_bits(bits: u64) -> Handle<T> {
+        Handle {
+            bits,
+            phantom: PhantomData,
+        }
+    }
+
+    /// The packed arena, index and generation
+    pub fn to_bits(self) -> u64 {
+        self.bits
+    }
+
+    /// Index of the entity
+    pub fn index(self) -> usize {
+        (self.bits >> INDEX_BITS) as usize & MAX_INDEX
+    }
+
+    /// Generation of the entity
+    pub fn generation(self) -> usize {
+        self.bits as usize & MAX_INDEX
+    }
+
+    /// Low 16 bits of the id of the arena the entity lives in
+    pub fn arena_tag(self) -> u16 {
+        (self.bits >> (2 * INDEX_BITS)) as u16
+    }
+
+    /// The same handle, for the entity at the same location in another arena
+    #[cfg(feature = "serde")]
+    pub(crate) fn with_arena(self, arena: ArenaId) -> Handle<T> {
+        Handle::new(self.index(), self.generation(), arena)
+    }
+}
+
+impl<T> Address<T> {
+    /// Get a handle to the entity, which does not keep it alive
+    ///
+    /// Panics if the index or generation does not fit in 24 bits, see `try_handle()`
+    pub fn handle(&self) -> Handle<T> {
+        Handle::new(self.index, self.generation, self.arena)
+    }
+
+    /// Same as `handle()`, giving None instead of panicking if the index or generation does not
+    /// fit in a handle
+    pub fn try_handle(&self) -> Option<Handle<T>> {
+        Handle::try_new(self.index, self.generation, self.arena)
+    }
+}
+
+impl<T> WeakAddress<T> {
+    /// Get a handle to the entity
+    ///
+    /// Panics if the index or generation does not fit in 24 bits, see `try_handle()`
+    pub fn handle(&self) -> Handle<T> {
+        Handle::new(self.index, self.generation, self.arena)
+    }
+
+    /// Same as `handle()`, giving None instead of panicking if the index or generation does not
+    /// fit in a handle
+    pub fn try_handle(&self) -> Option<Handle<T>> {
+        Handle::try_new(self.index, self.generation, self.arena)
+    }
+}
+
+impl<T> From<&Address<T>> for Handle<T> {
+    fn from(address: &Address<T>) -> Self {
+        address.handle()
+    }
+}
+
+impl<T> From<WeakAddress<T>> for Handle<T> {
+    fn from(address: WeakAddress<T>) -> Self {
+        address.handle()
+    }
+}
+
+impl<T> Locator<T> for Handle<T> {
+    fn locate(&self) -> (usize, usize) {
+        (self.index(), self.generation())
+    }
+
+    fn arena_tag(&self) -> Option<u16> {
+        Some(Handle::arena_tag(*self))
+    }
+}
+
+impl<T> Locator<T> for &Handle<T> {
+    fn locate(&self) -> (usize, usize) {
+        (self.index(), self.generation())
+    }
+
+    fn arena_tag(&self) -> Option<u16> {
+        Some(Handle::arena_tag(**self))
+    }
+}
+
+impl<T> Locator<T> for &Address<T> {
+    fn locate(&self) -> (usize, usize) {
+        (self.index, self.generation)
+    }
+
+    fn arena(&self) -> Option<ArenaId> {
+        Some(self.arena)
+    }
+}
+
+impl<T> Locator<T> for &WeakAddress<T> {
+    fn locate(&self) -> (usize, usize) {
+        (self.index, self.generation)
+    }
+
+    fn arena(&self) -> Option<ArenaId> {
+        Some(self.arena)
+    }
+}
+
+impl<T> Clone for Handle<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Handle<T> {}
+
+impl<T> PartialEq for Handle<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.bits == other.bits
+    }
+}
+
+impl<T> Eq for Handle<T> {}
+
+impl<T> PartialOrd for Handle<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T> Ord for Handle<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.bits.cmp(&other.bits)
+    }
+}
+
+impl<T> Hash for Handle<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.bits.hash(state)
+    }
+}
+
+impl<T> fmt::Debug for Handle<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Handle")
+            .field("arena_tag", &self.arena_tag())
+            .field("index", &self.index())
+            .field("generation", &self.generation())
+            .finish()
+    }
+}
@@ -0,0 +1,612 @@
+/*!
+This module implements saving and loading a whole arena with serde, enabled by the `serde` feature.
+
+The arena does not know the types it holds, so they are listed in a `Registry`, as a tuple of
+every type to save. Every location of every listed type is saved, vacant ones included, so the
+generations and the free list survive the round trip and addresses saved inside entities keep
+pointing to the same entities once loaded. Addresses are saved as their index and generation, and
+can only be loaded as part of an arena.
+
+Reference counts are restored as they were saved. Addresses that lived outside the arena at the
+time of saving are not part of the save, so the entities they owned are kept alive until they are
+removed
+```rust
+use arena_allocator::{Address, Arena, Registry, WeakAddress};
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize)]
+struct Health {value: i8}
+#[derive(Serialize, Deserialize)]
+struct Human {name: String, health: Address<Health>}
+#[derive(Serialize, Deserialize)]
+struct Monster {name: String, target: Option<WeakAddress<Human>>}
+
+let mut arena = Arena::default();
+let health = arena.allocate(Health{value: 100});
+let human = arena.allocate(Human{name: format!("Nader"), health});
+let monster = arena.allocate(Monster{name: format!("Borrow checker"), target: Some(human.downgrade())});
+// leaves a vacant location behind
+arena.allocate(Health{value: 1});
+
+let registry = Registry::<(Health, Human, Monster)>::new();
+let saved = serde_json::to_string(&registry.save(&arena)).unwrap();
+let mut loaded = registry.load(&mut serde_json::Deserializer::from_str(&saved)).unwrap();
+
+let (_, monster) = loaded.iter::<Monster>().next().unwrap();
+let target = monster.target.clone().unwrap();
+assert_eq!(monster.name, "Borrow checker");
+assert_eq!(target.get().unwrap().name, "Nader");
+assert_eq!(target.get().unwrap().health.get().unwrap().value, 100);
+drop(monster);
+
+// the vacant location is reused first, just like it would be in the saved arena
+let health = loaded.allocate(Health{value: 50});
+assert_eq!((health.index, health.generation), (1, 1));
+```
+Handles saved along with the arena resolve in the loaded arena
+```rust
+use arena_allocator::{Arena, Handle, Registry};
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize)]
+struct Dog {name: String, friend: Option<Handle<Dog>>}
+
+let mut arena = Arena::default();
+let bruce = arena.allocate(Dog{name: format!("Bruce"), friend: None});
+let rex = arena.allocate(Dog{name: format!("Rex"), friend: Some(bruce.handle())});
+
+let registry = Registry::<(Dog,)>::new();
+let saved = serde_json::to_string(&registry.save(&arena)).unwrap();
+let loaded = registry.load(&mut serde_json::Deserializer::from_str(&saved)).unwrap();
+let (_, rex) = loaded.iter::<Dog>().nth(1).unwrap();
+assert_eq!(loaded.get(rex.friend.unwrap()).unwrap().name, "Bruce");
+```
+while handles to other arenas are left pointing to those arenas
+```rust
+use arena_allocator::{Arena, ArenaError, Handle, Registry};
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize)]
+struct Dog {name: String, friend: Option<Handle<Dog>>}
+
+let mut street = Arena::default();
+let stray = street.allocate(Dog{name: format!("Stray"), friend: None});
+let mut arena = Arena::default();
+let bruce = arena.allocate(Dog{name: format!("Bruce"), friend: Some(stray.handle())});
+
+let registry = Registry::<(Dog,)>::new();
+let saved = serde_json::to_string(&registry.save(&arena)).unwrap();
+let loaded = registry.load(&mut serde_json::Deserializer::from_str(&saved)).unwrap();
+let (_, bruce) = loaded.iter::<Dog>().next().unwrap();
+let friend = bruce.friend.unwrap();
+assert!(matches!(loaded.try_get(friend), Err(ArenaError::HandleMismatch { .. })));
+assert_eq!(street.get(friend).unwrap().name, "Stray");
+```
+Addresses can not be loaded on their own
+```rust
+use arena_allocator::Address;
+assert!(serde_json::from_str::<Address<u8>>("[0, 0]").is_err());
+```
+Loading fails if the saved settings are not valid
+```rust
+use arena_allocator::Registry;
+
+let registry = Registry::<(u8,)>::new();
+let group = r#"{"first_free":null,"generation_floor":0,"locations":[]}"#;
+let load = |config: &str| {
+    let saved = format!(r#"{{"config":{},"groups":[{}]}}"#, config, group);
+    registry.load(&mut serde_json::Deserializer::from_str(&saved))
+};
+assert!(load(r#"{"storage":{"Paged":{"page_size":8}}}"#).is_ok());
+assert!(load(r#"{"storage":{"Paged":{"page_size":0}}}"#).is_err());
+assert!(load(r#"{"generation_bits":200}"#).is_err());
+```
+as well as if the list of free locations goes through a location in use, or loops
+```rust
+use arena_allocator::Registry;
+
+let registry = Registry::<(u8,)>::new();
+let load = |first_free: &str, next_free: &str| {
+    let saved = format!(
+        r#"{{"config":{{}},"groups":[{{"first_free":{},"generation_floor":0,"locations":[
+            {{"generation":0,"slot":{{"Occupied":{{"entity":5,"ref_count":1}}}}}},
+            {{"generation":1,"slot":{{"Vacant":{{"next_free":{}}}}}}}
+        ]}}]}}"#,
+        first_free, next_free
+    );
+    registry.load(&mut serde_json::Deserializer::from_str(&saved))
+};
+assert!(load("1", "null").is_ok());
+assert!(load("1", "0").is_err());
+assert!(load("1", "1").is_err());
+assert!(load("0", "null").is_err());
+assert!(load("2", "null").is_err());
+```
+ */
+
+#![forbid(missing_docs, missing_debug_implementations)]
+
+use std::any::TypeId;
+use std::cell::{Cell, RefCell};
+use std::collections::HashSet;
+use std::fmt;
+use std::marker::PhantomData;
+use std::mem;
+use std::rc::Rc;
+
+use serde::de::{self, DeserializeOwned, DeserializeSeed, MapAccess, SeqAccess, Visitor};
+use serde::ser::{self, SerializeStruct};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use super::address::{Address, WeakAddress};
+use super::arena::{Arena, Location, LocationGroup, Slot};
+use super::config::ArenaConfig;
+use super::handle::Handle;
+use super::storage::{Locations, Storage};
+
+/// Types of the addresses found while loading
+type AddressTypes = HashSet<TypeId>;
+
+/// Puts the loaded groups in the arena, once every entity is loaded
+type Restore = Box<dyn FnOnce(&mut Arena, &mut AddressTypes) -> Result<(), String>>;
+
+thread_local! {
+    /// The arena being loaded on this thread. Addresses loaded along with it point into it
+    static LOADING: RefCell<Option<LoadContext>> = const { RefCell::new(None) };
+}
+
+struct LoadContext {
+    arena: Arena,
+    address_types: AddressTypes,
+    /// Tag of the saved arena, handles with this tag are moved to the loaded arena
+    saved_tag: Option<u16>,
+}
+
+/// Marks the arena being loaded on this thread, the previous one is put back when dropped, so
+/// loading can happen inside of loading, and a panic while loading does not leave it behind
+struct Loading {
+    previous: Option<Option<LoadContext>>,
+}
+
+/// The list of types saved and loaded along with an arena, as a tuple of those types
+pub struct Registry<Types> {
+    types: PhantomData<Types>,
+}
+
+/// A tuple of types that can be saved and loaded, implemented for tuples of up to 12 types
+pub trait RegisteredTypes {
+    /// Saves the group of every type
+    #[doc(hidden)]
+    fn save_groups<S: Serializer>(arena: &Arena, serializer: S) -> Result<S::Ok, S::Error>;
+
+    /// Loads the group of every type
+    #[doc(hidden)]
+    fn load_groups<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Restore, D::Error>;
+}
+
+/// An arena ready to be saved, created by `Registry::save()`
+pub struct SavedArena<'a, Types> {
+    arena: &'a Arena,
+    types: PhantomData<Types>,
+}
+
+struct SavedGroups<'a, Types>(&'a Arena, PhantomData<Types>);
+
+struct SavedGroup<'a, T>(Option<&'a LocationGroup<T>>);
+
+struct SavedLocation<'a, T>(&'a Location<T>);
+
+#[derive(Serialize)]
+#[serde(rename = "Slot")]
+enum SavedSlot<'a, T> {
+    Occupied { entity: &'a T, ref_count: usize },
+    Vacant { next_free: Option<usize> },
+    Retired,
+}
+
+#[derive(Deserialize)]
+#[serde(rename = "Group")]
+struct LoadedGroup<T> {
+    first_free: Option<usize>,
+    generation_floor: usize,
+    locations: Vec<LoadedLocation<T>>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename = "Location")]
+struct LoadedLocation<T> {
+    generation: usize,
+    slot: LoadedSlot<T>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename = "Slot")]
+enum LoadedSlot<T> {
+    Occupied { entity: T, ref_count: usize },
+    Vacant { next_free: Option<usize> },
+    Retired,
+}
+
+struct ArenaVisitor<Types>(PhantomData<Types>);
+
+struct GroupsSeed<Types>(PhantomData<Types>);
+
+impl<Types: RegisteredTypes> Registry<Types> {
+    /// Creates a registry of the types in `Types`
+    pub fn new() -> Registry<Types> {
+        Registry { types: PhantomData }
+    }
+
+    /// Prepares an arena to be saved with any serializer. Entities are borrowed while saving,
+    /// saving fails if any of them is mutably borrowed
+    pub fn save<'a>(&self, arena: &'a Arena) -> SavedArena<'a, Types> {
+        SavedArena {
+            arena,
+            types: PhantomData,
+        }
+    }
+
+    /// Loads an arena saved with the same list of types
+    pub fn load<'de, D: Deserializer<'de>>(&self, deserializer: D) -> Result<Arena, D::Error> {
+        let loading = Loading::start(LoadContext {
+            arena: Arena::default(),
+            address_types: HashSet::new(),
+            saved_tag: None,
+        });
+        let loaded = deserializer.deserialize_struct(
+            "Arena",
+            &["config", "tag", "groups"],
+            ArenaVisitor::<Types>(PhantomData),
+        );
+        let LoadContext {
+            mut arena,
+            mut address_types,
+            ..
+        } = loading.finish();
+        let (config, restore) = loaded?;
+        config.validate().map_err(de::Error::custom)?;
+        arena.config = config;
+        restore(&mut arena, &mut address_types).map_err(de::Error::custom)?;
+        // what is left points to types the registry does not have
+        if !address_types.is_empty() {
+            return Err(de::Error::custom(
+                "found an address to a type that is not in the registry",
+            ));
+        }
+        Ok(arena)
+    }
+}
+
+impl Loading {
+    fn start(context: LoadContext) -> Loading {
+        let previous = LOADING.with(|loading| loading.replace(Some(context)));
+        Loading {
+            previous: Some(previous),
+        }
+    }
+
+    fn finish(mut self) -> LoadContext {
+        let previous = self.previous.take().unwrap();
+        LOADING.with(|loading| loading.replace(previous)).unwrap()
+    }
+}
+
+impl Drop for Loading {
+    fn drop(&mut self) {
+        if let Some(previous) = self.previous.take() {
+            LOADING.with(|loading| loading.replace(previous));
+        }
+    }
+}
+
+/// Runs a function with the arena being loaded on this thread, None if nothing is being loaded
+fn with_load_context<R>(f: impl FnOnce(&mut LoadContext) -> R) -> Option<R> {
+    LOADING.with(|loading| loading.borrow_mut().as_mut().map(f))
+}
+
+impl<T: 'static> LoadedGroup<T> {
+    /// Checks that the list of free locations only goes through vacant locations, each of them
+    /// once, so allocating never overwrites a loaded entity
+    fn check_free_list(&self) -> Result<(), String> {
+        let mut visited = vec![false; self.locations.len()];
+        let mut next = self.first_free;
+        while let Some(index) = next {
+            let location = match self.locations.get(index) {
+                Some(location) => location,
+                None => return Err("found a free location past the end of the arena".to_string()),
+            };
+            if mem::replace(&mut visited[index], true) {
+                return Err("found a loop in the list of free locations".to_string());
+            }
+            next = match &location.slot {
+                LoadedSlot::Vacant { next_free } => *next_free,
+                LoadedSlot::Occupied { .. } | LoadedSlot::Retired => {
+                    return Err("found a location in use in the list of free locations".to_string())
+                }
+            };
+        }
+        Ok(())
+    }
+
+    /// Replaces the locations of the group of `T` in the arena with the loaded ones, along with
+    /// the reference counts of their entities
+    fn restore(self, arena: &mut Arena, address_types: &mut AddressTypes) -> Result<(), String> {
+        self.check_free_list()?;
+        let type_id = TypeId::of::<T>();
+        let len = self.locations.len();
+
+        let config = arena.config;
+        let group = Rc::clone(arena.group_or_insert::<T>());
+        let capacity = match arena.type_capacities.get(&type_id) {
+            Some(&capacity) => capacity,
+            None => config.capacity,
+        };
+        let mut locations = Locations::new(config.storage, capacity.max(len));
+        for location in self.locations {
+            let (slot, ref_count) = match location.slot {
+                LoadedSlot::Occupied { entity, ref_count } => (Slot::Occupied(entity), ref_count),
+                LoadedSlot::Vacant { next_free } => (Slot::Vacant { next_free }, 0),
+                LoadedSlot::Retired => (Slot::Retired, 0),
+            };
+            locations.push(Location {
+                generation: Cell::new(location.generation),
+                ref_count: Cell::new(ref_count),
+                slot: RefCell::new(slot),
+            });
+        }
+        // addresses to freed entities, or to locations dropped by compacting, simply do not
+        // resolve
+        address_types.remove(&type_id);
+
+        // SAFETY: the arena is not handed out until loading is done, nothing references the list
+        unsafe { *group.locations_mut() = locations };
+        group.first_free.set(self.first_free);
+        group.generation_floor.set(self.generation_floor);
+        // the group may have been created by a loaded address, before the config was known
+        group.max_generation.set(config.max_generation());
+        group.grow.set(config.grow);
+        Ok(())
+    }
+}
+
+impl<Types> fmt::Debug for Registry<Types> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Registry").finish()
+    }
+}
+
+impl<Types: RegisteredTypes> Default for Registry<Types> {
+    fn default() -> Self {
+        Registry::new()
+    }
+}
+
+impl<Types> fmt::Debug for SavedArena<'_, Types> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SavedArena")
+            .field("arena", self.arena)
+            .finish()
+    }
+}
+
+impl<Types: RegisteredTypes> Serialize for SavedArena<'_, Types> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("Arena", 3)?;
+        state.serialize_field("config", &self.arena.config)?;
+        // saved before the groups, so it is known by the time their handles are loaded
+        state.serialize_field("tag", &self.arena.id().tag())?;
+        state.serialize_field("groups", &SavedGroups(self.arena, self.types))?;
+        state.end()
+    }
+}
+
+impl<Types: RegisteredTypes> Serialize for SavedGroups<'_, Types> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        Types::save_groups(self.0, serializer)
+    }
+}
+
+impl<T: Serialize> Serialize for SavedGroup<'_, T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let (first_free, generation_floor, locations) = match self.0 {
+            Some(group) => (
+                group.first_free.get(),
+                group.generation_floor.get(),
+                group.locations(),
+            ),
+            None => (None, 0, &Locations::new(Storage::Contiguous, 0)),
+        };
+        let mut state = serializer.serialize_struct("Group", 3)?;
+        state.serialize_field("first_free", &first_free)?;
+        state.serialize_field("generation_floor", &generation_floor)?;
+        state.serialize_field("locations", &SavedLocations(locations))?;
+        state.end()
+    }
+}
+
+struct SavedLocations<'a, T>(&'a Locations<T>);
+
+impl<T: Serialize> Serialize for SavedLocations<'_, T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.0.iter().map(SavedLocation))
+    }
+}
+
+impl<T: Serialize> Serialize for SavedLocation<'_, T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let location = self.0;
+        let slot = location.slot.try_borrow().map_err(|_| {
+            ser::Error::custom("can not save an entity while it is mutably borrowed")
+        })?;
+        let saved = match &*slot {
+            Slot::Occupied(entity) => SavedSlot::Occupied {
+                entity,
+                ref_count: location.ref_count.get(),
+            },
+            Slot::Vacant { next_free } => SavedSlot::Vacant {
+                next_free: *next_free,
+            },
+            Slot::Retired => SavedSlot::Retired,
+        };
+        let mut state = serializer.serialize_struct("Location", 2)?;
+        state.serialize_field("generation", &location.generation.get())?;
+        state.serialize_field("slot", &saved)?;
+        state.end()
+    }
+}
+
+impl<'de, Types: RegisteredTypes> Visitor<'de> for ArenaVisitor<Types> {
+    type Value = (ArenaConfig, Restore);
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "a saved arena")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let config = seq
+            .next_element()?
+            .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+        let tag = seq
+            .next_element()?
+            .ok_or_else(|| de::Error::invalid_length(1, &self))?;
+        with_load_context(|context| context.saved_tag = Some(tag));
+        let groups = seq
+            .next_element_seed(GroupsSeed::<Types>(PhantomData))?
+            .ok_or_else(|| de::Error::invalid_length(2, &self))?;
+        Ok((config, groups))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut config = None;
+        let mut groups = None;
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                "config" => config = Some(map.next_value()?),
+                "tag" => {
+                    let tag = map.next_value()?;
+                    with_load_context(|context| context.saved_tag = Some(tag));
+                }
+                "groups" => groups = Some(map.next_value_seed(GroupsSeed::<Types>(PhantomData))?),
+                _ => {
+                    map.next_value::<de::IgnoredAny>()?;
+                }
+            }
+        }
+        let config = config.ok_or_else(|| de::Error::missing_field("config"))?;
+        let groups = groups.ok_or_else(|| de::Error::missing_field("groups"))?;
+        Ok((config, groups))
+    }
+}
+
+impl<'de, Types: RegisteredTypes> DeserializeSeed<'de> for GroupsSeed<Types> {
+    type Value = Restore;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Restore, D::Error> {
+        Types::load_groups(deserializer)
+    }
+}
+
+impl<T: 'static> Serialize for Address<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        (self.index, self.generation).serialize(serializer)
+    }
+}
+
+impl<T: 'static> Serialize for WeakAddress<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        (self.index, self.generation).serialize(serializer)
+    }
+}
+
+impl<T: 'static> Serialize for Handle<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.to_bits().serialize(serializer)
+    }
+}
+
+/// Handles do not point into an arena, so they can be loaded on their own. Loaded along with an
+/// arena, the handles of the saved arena are moved to the loaded arena, handles of other arenas
+/// are left as they are
+impl<'de, T: 'static> Deserialize<'de> for Handle<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let handle = Handle::from_bits(u64::deserialize(deserializer)?);
+        let moved = with_load_context(|context| {
+            (context.saved_tag == Some(handle.arena_tag()))
+                .then(|| handle.with_arena(context.arena.id()))
+        });
+        Ok(moved.flatten().unwrap_or(handle))
+    }
+}
+
+impl<'de, T: 'static> Deserialize<'de> for Address<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let (index, generation) = <(usize, usize)>::deserialize(deserializer)?;
+        with_load_context(|context| {
+            // the count of the entity is loaded along with it, the address is only checked
+            // against the loaded types
+            context.address_types.insert(TypeId::of::<T>());
+            let group = context.arena.group_or_insert::<T>();
+            Address {
+                generation,
+                index,
+                arena: group.arena,
+                group: Rc::downgrade(group),
+            }
+        })
+        .ok_or_else(|| de::Error::custom("addresses can only be loaded as part of an arena"))
+    }
+}
+
+impl<'de, T: 'static> Deserialize<'de> for WeakAddress<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let (index, generation) = <(usize, usize)>::deserialize(deserializer)?;
+        with_load_context(|context| {
+            // the type is checked against the loaded ones along with owning addresses
+            context.address_types.insert(TypeId::of::<T>());
+            let group = context.arena.group_or_insert::<T>();
+            WeakAddress {
+                generation,
+                index,
+                arena: group.arena,
+                group: Rc::downgrade(group),
+            }
+        })
+        .ok_or_else(|| de::Error::custom("addresses can only be loaded as part of an arena"))
+    }
+}
+
+macro_rules! registered_types {
+    ($($name:ident),+) => {
+        impl<$($name),+> RegisteredTypes for ($($name,)+)
+        where
+            $($name: Serialize + DeserializeOwned + 'static),+
+        {
+            fn save_groups<S: Serializer>(arena: &Arena, serializer: S) -> Result<S::Ok, S::Error> {
+                ($(SavedGroup(arena.find_group::<$name>().map(|group| &**group)),)+)
+                    .serialize(serializer)
+            }
+
+            #[allow(non_snake_case)]
+            fn load_groups<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Restore, D::Error> {
+                let ($($name,)+) = <($(LoadedGroup<$name>,)+)>::deserialize(deserializer)?;
+                Ok(Box::new(move |arena, address_types| {
+                    $($name.restore(arena, address_types)?;)+
+                    Ok(())
+                }))
+            }
+        }
+    };
+}
+
+registered_types!(T1);
+registered_types!(T1, T2);
+registered_types!(T1, T2, T3);
+registered_types!(T1, T2, T3, T4);
+registered_types!(T1, T2, T3, T4, T5);
+registered_types!(T1, T2, T3, T4, T5, T6);
+registered_types!(T1, T2, T3, T4, T5, T6, T7);
+registered_types!(T1, T2, T3, T4, T5, T6, T7, T8);
+registered_types!(T1, T2, T3, T4, T5, T6, T7, T8, T9);
+registered_types!(T1, T2, T3, T4, T5, T6, T7, T8, T9, T10);
+registered_types!(T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11);
+registered_types!(T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12);
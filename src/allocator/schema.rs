@@ -0,0 +1,248 @@
+/*!
+This module implements schemas, structs holding one typed arena per entity type, generated by the
+`arena_schema!` macro.
+
+When every entity type is known up front, a schema finds the pool of a type at compile time. There
+is no table of types to look up and no type that can be missing from it, and the addresses are the
+same `Address<T>` handed out by any other arena
+```rust
+use arena_allocator::{arena_schema, Address};
+
+struct Health {value: i8}
+struct Human {name: String, health: Address<Health>}
+struct Monster {name: String, health: Address<Health>}
+
+arena_schema! {
+    /// Everything in the game
+    pub struct World {
+        humans: Human,
+        monsters: Monster,
+        health: Health,
+    }
+}
+
+let mut world = World::new(16);
+let health = world.allocate(Health{value: 100});
+let human = world.allocate(Human{name: format!("Nader"), health});
+let health = world.allocate(Health{value: 50});
+let monster = world.allocate(Monster{name: format!("Borrow checker"), health});
+
+world.get(&human).unwrap().health.get_mut().unwrap().value -= 5;
+assert_eq!(world.get(&human).unwrap().health.get().unwrap().value, 95);
+assert_eq!(world.get_mut(&monster).unwrap().name, "Borrow checker");
+
+// every pool is a field of its own
+assert_eq!(world.monsters.iter().count(), 1);
+assert_eq!(world.stats().live(), 4);
+```
+A schema can also be declared by listing the types alone. The struct is then called `Schema`, and
+each pool field is named after its type
+```rust
+use arena_allocator::{arena_schema, World};
+
+struct Human {name: String}
+struct Monster {name: String}
+
+arena_schema! { Human, Monster }
+
+let mut schema = Schema::default();
+let human = schema.allocate(Human{name: format!("Nader")});
+assert_eq!(schema.Human.get(&human).unwrap().name, "Nader");
+
+// the entity component system of the crate is another world
+let mut world = World::new();
+world.spawn();
+```
+Only the listed types can be used with the schema
+```rust,compile_fail
+use arena_allocator::arena_schema;
+
+struct Human;
+struct Monster;
+
+arena_schema! { Human }
+
+let mut schema = Schema::default();
+schema.allocate(Monster);
+```
+ */
+
+#![forbid(missing_docs, missing_debug_implementations)]
+
+use super::typed_arena::TypedArena;
+
+/// A struct holding the typed arena of entities of type `T`, implemented by `arena_schema!` for
+/// every type of the schema
+pub trait Pool<T: 'static> {
+    /// The arena of entities of type `T`
+    fn pool(&self) -> &TypedArena<T>;
+
+    /// The arena of entities of type `T`, mutably
+    fn pool_mut(&mut self) -> &mut TypedArena<T>;
+}
+
+/// Declares a struct with one `TypedArena` field per entity type, along with methods that pick
+/// the arena of a type at compile time. See the `schema` module for examples
+#[macro_export]
+macro_rules! arena_schema {
+    (
+        $(#[$meta:meta])*
+        $vis:vis struct $name:ident {
+            $($field:ident: $ty:ty),+ $(,)?
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Debug)]
+        $vis struct $name {
+            $(
+                #[allow(missing_docs)]
+                $vis $field: $crate::TypedArena<$ty>,
+            )+
+        }
+
+        $(
+            impl $crate::Pool<$ty> for $name {
+                #[inline]
+                fn pool(&self) -> &$crate::TypedArena<$ty> {
+                    &self.$field
+                }
+
+                #[inline]
+                fn pool_mut(&mut self) -> &mut $crate::TypedArena<$ty> {
+                    &mut self.$field
+                }
+            }
+        )+
+
+        #[allow(dead_code)]
+        impl $name {
+            /// Creates every arena of the schema with a given capacity
+            $vis fn new(capacity: usize) -> $name {
+                $name {
+                    $($field: $crate::TypedArena::new(capacity),)+
+                }
+            }
+
+            /// Creates every arena of the schema with the given settings
+            $vis fn with_config(config: $crate::ArenaConfig) -> $name {
+                $name {
+                    $($field: $crate::TypedArena::with_config(config),)+
+                }
+            }
+
+            /// The arena of entities of type `T`
+            #[inline]
+            $vis fn pool<T: 'static>(&self) -> &$crate::TypedArena<T>
+            where
+                Self: $crate::Pool<T>,
+            {
+                $crate::Pool::<T>::pool(self)
+            }
+
+            /// The arena of entities of type `T`, mutably
+            #[inline]
+            $vis fn pool_mut<T: 'static>(&mut self) -> &mut $crate::TypedArena<T>
+            where
+                Self: $crate::Pool<T>,
+            {
+                $crate::Pool::<T>::pool_mut(self)
+            }
+
+            /// Adds a new entity to the arena of its type, see `Arena::allocate()`
+            #[inline]
+            $vis fn allocate<T: 'static>(&mut self, v: T) -> $crate::Address<T>
+            where
+                Self: $crate::Pool<T>,
+            {
+                self.pool_mut::<T>().allocate(v)
+            }
+
+            /// Adds every entity of an iterator, see `Arena::allocate_many()`
+            $vis fn allocate_many<T: 'static, I: IntoIterator<Item = T>>(
+                &mut self,
+                values: I,
+            ) -> Vec<$crate::Address<T>>
+            where
+                Self: $crate::Pool<T>,
+            {
+                self.pool_mut::<T>().allocate_many(values)
+            }
+
+            /// Borrow the entity at a given address or handle, see `Arena::get()`
+            #[inline]
+            $vis fn get<T: 'static>(
+                &self,
+                address: impl $crate::Locator<T>,
+            ) -> Option<$crate::Ref<'_, T>>
+            where
+                Self: $crate::Pool<T>,
+            {
+                self.pool::<T>().get(address)
+            }
+
+            /// Mutably borrow the entity at a given address or handle, see `Arena::get_mut()`
+            #[inline]
+            $vis fn get_mut<T: 'static>(
+                &mut self,
+                address: impl $crate::Locator<T>,
+            ) -> Option<$crate::RefMut<'_, T>>
+            where
+                Self: $crate::Pool<T>,
+            {
+                self.pool_mut::<T>().get_mut(address)
+            }
+
+            /// Free the entity at a given address or handle, see `Arena::free()`
+            #[inline]
+            $vis fn free<T: 'static>(&self, address: impl $crate::Locator<T>)
+            where
+                Self: $crate::Pool<T>,
+            {
+                self.pool::<T>().free(address)
+            }
+
+            /// Iterate over every live entity of a type, see `Arena::iter()`
+            $vis fn iter<T: 'static>(&self) -> $crate::Iter<'_, T>
+            where
+                Self: $crate::Pool<T>,
+            {
+                self.pool::<T>().iter()
+            }
+
+            /// Iterate over every live entity of a type with mutable access, see
+            /// `Arena::iter_mut()`
+            $vis fn iter_mut<T: 'static>(&mut self) -> $crate::IterMut<'_, T>
+            where
+                Self: $crate::Pool<T>,
+            {
+                self.pool_mut::<T>().iter_mut()
+            }
+
+            /// Frees every entity of every type, see `Arena::clear_all()`
+            $vis fn clear_all(&mut self) {
+                $(self.$field.clear();)+
+            }
+
+            /// Statistics of every type, in the order they are listed in the schema
+            $vis fn stats(&self) -> $crate::ArenaStats {
+                $crate::ArenaStats {
+                    types: vec![$(self.$field.stats()),+],
+                }
+            }
+        }
+
+        impl Default for $name {
+            fn default() -> Self {
+                $name::with_config($crate::ArenaConfig::default())
+            }
+        }
+    };
+    ($($ty:ident),+ $(,)?) => {
+        $crate::arena_schema! {
+            #[allow(non_snake_case)]
+            pub struct Schema {
+                $($ty: $ty),+
+            }
+        }
+    };
+}
@@ -0,0 +1,141 @@
+/*!
+This module implements the statistics of an arena, as reported by `Arena::stats()`.
+
+Statistics are a snapshot, gathered by going over every location of every type in the arena, so
+they are meant for debugging and occasional reporting rather than for every frame
+```rust
+use arena_allocator::Arena;
+let mut arena = Arena::new(4);
+
+struct Dog {name: String}
+struct Cat {name: String}
+
+let bruce = arena.allocate(Dog{name: format!("Bruce")});
+let rex = arena.allocate(Dog{name: format!("Rex")});
+let tom = arena.allocate(Cat{name: format!("Tom")});
+rex.remove();
+
+let stats = arena.stats();
+let dogs = stats.of::<Dog>().unwrap();
+assert_eq!((dogs.live, dogs.freed, dogs.slots, dogs.capacity), (1, 1, 2, 4));
+assert_eq!(dogs.max_generation, 1);
+assert_eq!(stats.live(), 2);
+assert_eq!(stats.freed(), 1);
+// Display gives one line per type, followed by the totals
+assert_eq!(stats.to_string().lines().count(), 3);
+```
+ */
+
+#![forbid(missing_docs, missing_debug_implementations)]
+
+use std::any::{self, TypeId};
+use std::fmt;
+
+/// Statistics of every type in an arena, in the order the types were first allocated
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ArenaStats {
+    /// Statistics of each type
+    pub types: Vec<TypeStats>,
+}
+
+/// Statistics of a single type in an arena
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TypeStats {
+    /// Id of the type
+    pub type_id: TypeId,
+    /// Name of the type, as given by `std::any::type_name()`
+    pub type_name: &'static str,
+    /// Number of locations holding an entity
+    pub live: usize,
+    /// Number of freed locations waiting to be reused
+    pub freed: usize,
+    /// Number of locations that ran out of generations, see `ArenaConfig::generation_bits`
+    pub retired: usize,
+    /// Number of locations, live, freed and retired
+    pub slots: usize,
+    /// Number of locations the list can hold before it has to grow
+    pub capacity: usize,
+    /// Highest generation of any location
+    pub max_generation: usize,
+    /// Approximate bytes taken by the list of locations, the generation, reference count and
+    /// borrow flag of every location included. Memory owned by the entities themselves is not
+    pub bytes: usize,
+}
+
+impl ArenaStats {
+    /// Statistics of a single type, None if no entity of the type was ever allocated
+    pub fn of<T: 'static>(&self) -> Option<&TypeStats> {
+        self.types
+            .iter()
+            .find(|stats| stats.type_id == TypeId::of::<T>())
+    }
+
+    /// Number of live entities of all types
+    pub fn live(&self) -> usize {
+        self.types.iter().map(|stats| stats.live).sum()
+    }
+
+    /// Number of freed locations of all types
+    pub fn freed(&self) -> usize {
+        self.types.iter().map(|stats| stats.freed).sum()
+    }
+
+    /// Number of locations of all types
+    pub fn slots(&self) -> usize {
+        self.types.iter().map(|stats| stats.slots).sum()
+    }
+
+    /// Approximate bytes taken by the locations of all types
+    pub fn bytes(&self) -> usize {
+        self.types.iter().map(|stats| stats.bytes).sum()
+    }
+}
+
+impl TypeStats {
+    pub(crate) fn new<T: 'static>() -> TypeStats {
+        TypeStats {
+            type_id: TypeId::of::<T>(),
+            type_name: any::type_name::<T>(),
+            live: 0,
+            freed: 0,
+            retired: 0,
+            slots: 0,
+            capacity: 0,
+            max_generation: 0,
+            bytes: 0,
+        }
+    }
+}
+
+impl fmt::Display for ArenaStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for stats in &self.types {
+            writeln!(f, "{}", stats)?;
+        }
+        write!(
+            f,
+            "total: {} live, {} freed, {} slots, {} bytes",
+            self.live(),
+            self.freed(),
+            self.slots(),
+            self.bytes()
+        )
+    }
+}
+
+impl fmt::Display for TypeStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: {} live, {} freed, {} retired, {}/{} slots, generation {}, {} bytes",
+            self.type_name,
+            self.live,
+            self.freed,
+            self.retired,
+            self.slots,
+            self.capacity,
+            self.max_generation,
+            self.bytes
+        )
+    }
+}
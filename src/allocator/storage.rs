@@ -0,0 +1,320 @@
+/*!
+This module implements the ways the locations of a type can be stored in memory.
+
+By default every type keeps its locations in a single contiguous list. Growing that list moves
+every entity, so the arena refuses to grow while any of them is borrowed. With `Storage::Paged`
+the locations are kept in pages of a fixed size instead, and running out of room adds a page
+rather than moving the ones already there. Entities stay in place for as long as they live, and
+borrows of them can be held across allocations. Indexes, generations and the order of iteration
+are the same for both
+```rust
+use arena_allocator::{Arena, ArenaConfig, Storage};
+let mut arena = Arena::with_config(ArenaConfig {
+    capacity: 2,
+    storage: Storage::Paged { page_size: 2 },
+    ..ArenaConfig::default()
+});
+
+struct Dog {name: String}
+
+let bruce = arena.allocate(Dog{name: format!("Bruce")});
+let guard = bruce.get().unwrap();
+let place = &*guard as *const Dog;
+// allocating past the first page while bruce is borrowed
+let dogs: Vec<_> = (0..10)
+    .map(|i| arena.allocate(Dog{name: format!("Dog #{}", i)}))
+    .collect();
+assert_eq!(&*guard as *const Dog, place);
+assert_eq!(guard.name, "Bruce");
+drop(guard);
+
+assert_eq!(dogs[9].index, 10);
+assert_eq!(arena.stats().of::<Dog>().unwrap().capacity, 12);
+let names: Vec<_> = arena.iter::<Dog>().map(|(_, dog)| dog.name.clone()).collect();
+assert_eq!(names[..3], ["Bruce", "Dog #0", "Dog #1"]);
+```
+With contiguous storage the same allocations would have to move bruce
+```rust,should_panic
+use arena_allocator::Arena;
+let mut arena = Arena::new(2);
+
+struct Dog {name: String}
+
+let bruce = arena.allocate(Dog{name: format!("Bruce")});
+let guard = bruce.get().unwrap();
+let dogs: Vec<_> = (0..10)
+    .map(|i| arena.allocate(Dog{name: format!("Dog #{}", i)}))
+    .collect();
+```
+ */
+
+#![forbid(missing_docs, missing_debug_implementations)]
+
+use std::ops::{Index, IndexMut};
+
+use super::arena::Location;
+
+/// How the locations of each type are laid out in memory, see `ArenaConfig::storage`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Storage {
+    /// A single list per type, which is reallocated as it grows, moving every entity in it. The
+    /// default
+    #[default]
+    Contiguous,
+    /// Pages of `page_size` locations per type. New pages are added as needed, entities never
+    /// move unless the arena is compacted
+    Paged {
+        /// Number of locations in each page, must be at least 1
+        page_size: usize,
+    },
+}
+
+/// The locations of a group, stored as chosen by `Storage`
+pub(crate) enum Locations<T> {
+    Contiguous(Vec<Location<T>>),
+    /// Every page is allocated with room for `page_size` locations and never grows past it, so
+    /// that the locations in it never move. Pages are filled in order, the ones past `len` are
+    /// empty
+    Paged {
+        pages: Vec<Vec<Location<T>>>,
+        page_size: usize,
+        len: usize,
+    },
+}
+
+/// Iterator over the locations of a group, in the order of their indexes
+pub(crate) struct LocationsIter<'a, T> {
+    locations: &'a Locations<T>,
+    front: usize,
+    back: usize,
+}
+
+impl<T> Locations<T> {
+    /// Empty locations, with room for at least `capacity` of them
+    pub(crate) fn new(storage: Storage, capacity: usize) -> Locations<T> {
+        match storage {
+            Storage::Contiguous => Locations::Contiguous(Vec::with_capacity(capacity)),
+            Storage::Paged { page_size } => {
+                let mut locations = Locations::Paged {
+                    pages: Vec::new(),
+                    page_size,
+                    len: 0,
+                };
+                locations.reserve(capacity);
+                locations
+            }
+        }
+    }
+
+    /// Number of locations
+    pub(crate) fn len(&self) -> usize {
+        match self {
+            Locations::Contiguous(list) => list.len(),
+            Locations::Paged { len, .. } => *len,
+        }
+    }
+
+    /// Whether there are no locations
+    pub(crate) fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Number of locations there is room for before more memory has to be allocated
+    pub(crate) fn capacity(&self) -> usize {
+        match self {
+            Locations::Contiguous(list) => list.capacity(),
+            Locations::Paged {
+                pages, page_size, ..
+            } => pages.len() * page_size,
+        }
+    }
+
+    /// Whether making room for more locations leaves the existing ones in place
+    pub(crate) fn is_stable(&self) -> bool {
+        matches!(self, Locations::Paged { .. })
+    }
+
+    /// The location at an index
+    pub(crate) fn get(&self, index: usize) -> Option<&Location<T>> {
+        match self {
+            Locations::Contiguous(list) => list.get(index),
+            Locations::Paged {
+                pages, page_size, ..
+            } => pages.get(index / page_size)?.get(index % page_size),
+        }
+    }
+
+    /// The location at an index, mutably
+    pub(crate) fn get_mut(&mut self, index: usize) -> Option<&mut Location<T>> {
+        match self {
+            Locations::Contiguous(list) => list.get_mut(index),
+            Locations::Paged {
+                pages, page_size, ..
+            } => pages
+                .get_mut(index / *page_size)?
+                .get_mut(index % *page_size),
+        }
+    }
+
+    /// Two distinct locations at once, mutably
+    ///
+    /// Panics if the indexes are the same or either is out of range
+    pub(crate) fn pair_mut(&mut self, a: usize, b: usize) -> (&mut Location<T>, &mut Location<T>) {
+        assert_ne!(a, b, "can not borrow the same location twice");
+        let a: *mut Location<T> = &mut self[a];
+        let b: *mut Location<T> = &mut self[b];
+        // SAFETY: the indexes differ, so the locations do not overlap
+        unsafe { (&mut *a, &mut *b) }
+    }
+
+    /// Pointer to the location at an index, without going through a reference to the page or
+    /// list it is in, so that references to other locations are left valid
+    ///
+    /// SAFETY: the index must be in range
+    pub(crate) unsafe fn location_ptr(&mut self, index: usize) -> *mut Location<T> {
+        match self {
+            Locations::Contiguous(list) => list.as_mut_ptr().add(index),
+            Locations::Paged {
+                pages, page_size, ..
+            } => pages[index / *page_size]
+                .as_mut_ptr()
+                .add(index % *page_size),
+        }
+    }
+
+    /// Adds a location at the end
+    pub(crate) fn push(&mut self, location: Location<T>) {
+        match self {
+            Locations::Contiguous(list) => list.push(location),
+            Locations::Paged {
+                pages,
+                page_size,
+                len,
+            } => {
+                let page = *len / *page_size;
+                if page == pages.len() {
+                    pages.push(Vec::with_capacity(*page_size));
+                }
+                pages[page].push(location);
+                *len += 1;
+            }
+        }
+    }
+
+    /// Removes the last location
+    pub(crate) fn pop(&mut self) -> Option<Location<T>> {
+        match self {
+            Locations::Contiguous(list) => list.pop(),
+            Locations::Paged {
+                pages,
+                page_size,
+                len,
+            } => {
+                if *len == 0 {
+                    return None;
+                }
+                *len -= 1;
+                pages[*len / *page_size].pop()
+            }
+        }
+    }
+
+    /// The last location
+    pub(crate) fn last(&self) -> Option<&Location<T>> {
+        self.get(self.len().checked_sub(1)?)
+    }
+
+    /// Makes room for at least `additional` more locations. Contiguous locations are moved if
+    /// the list is reallocated, paged ones get new pages
+    pub(crate) fn reserve(&mut self, additional: usize) {
+        match self {
+            Locations::Contiguous(list) => list.reserve(additional),
+            Locations::Paged {
+                pages,
+                page_size,
+                len,
+            } => {
+                let wanted = *len + additional;
+                while pages.len() * *page_size < wanted {
+                    pages.push(Vec::with_capacity(*page_size));
+                }
+            }
+        }
+    }
+
+    /// Drops the room left past the last location. Contiguous locations are moved if the list is
+    /// reallocated, for paged ones only the pages past the last location are dropped
+    pub(crate) fn shrink_to_fit(&mut self) {
+        match self {
+            Locations::Contiguous(list) => list.shrink_to_fit(),
+            Locations::Paged {
+                pages,
+                page_size,
+                len,
+            } => {
+                pages.truncate(len.div_ceil(*page_size));
+                pages.shrink_to_fit();
+            }
+        }
+    }
+
+    /// Every location, in the order of their indexes
+    pub(crate) fn iter(&self) -> LocationsIter<'_, T> {
+        LocationsIter {
+            locations: self,
+            front: 0,
+            back: self.len(),
+        }
+    }
+}
+
+impl<T> Index<usize> for Locations<T> {
+    type Output = Location<T>;
+
+    fn index(&self, index: usize) -> &Location<T> {
+        match self.get(index) {
+            Some(location) => location,
+            None => panic!("location {} is out of range of {}", index, self.len()),
+        }
+    }
+}
+
+impl<T> IndexMut<usize> for Locations<T> {
+    fn index_mut(&mut self, index: usize) -> &mut Location<T> {
+        let len = self.len();
+        match self.get_mut(index) {
+            Some(location) => location,
+            None => panic!("location {} is out of range of {}", index, len),
+        }
+    }
+}
+
+impl<'a, T> Iterator for LocationsIter<'a, T> {
+    type Item = &'a Location<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.front == self.back {
+            return None;
+        }
+        self.front += 1;
+        self.locations.get(self.front - 1)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.back - self.front;
+        (len, Some(len))
+    }
+}
+
+impl<T> DoubleEndedIterator for LocationsIter<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.front == self.back {
+            return None;
+        }
+        self.back -= 1;
+        self.locations.get(self.back)
+    }
+}
+
+impl<T> ExactSizeIterator for LocationsIter<'_, T> {}
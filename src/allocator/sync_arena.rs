@@ -0,0 +1,767 @@
+/*!
+This module implements a thread safe version of the arena.
+
+`SyncArena` has the same `allocate`/`get`/`free` surface as `Arena`, but it can be shared between
+threads, and so can its addresses. Every type gets its own lock, so threads working on different
+types never wait on each other. Generations and reference counts are atomics, which lets
+addresses be copied and dropped while other threads read the same type
+
+```rust
+use arena_allocator::{SyncAddress, SyncArena};
+use std::thread;
+
+struct Dog {name: String}
+
+let arena = SyncArena::default();
+let bruce = arena.allocate(Dog{name: format!("Bruce")});
+let handles: Vec<_> = (0..4)
+    .map(|_| {
+        let bruce = bruce.clone();
+        thread::spawn(move || bruce.get().unwrap().name.len())
+    })
+    .collect();
+for handle in handles {
+    assert_eq!(handle.join().unwrap(), 5);
+}
+```
+An arena only resolves its own addresses, and dropping the last address of an entity never waits
+for the lock of its type. If an entity of the type is borrowed at the time, the entity is freed
+once the borrow ends
+```rust
+use arena_allocator::SyncArena;
+use std::sync::Arc;
+
+struct Dog {name: String}
+
+let kennel = SyncArena::default();
+let shelter = SyncArena::default();
+let bruce = kennel.allocate(Dog{name: format!("Bruce")});
+let rex = shelter.allocate(Dog{name: format!("Rex")});
+assert!(kennel.get(&rex).is_none());
+shelter.free(&bruce);
+assert_eq!(bruce.get().unwrap().name, "Bruce");
+
+struct Toy;
+let toy = Arc::new(Toy);
+let ball = kennel.allocate(Arc::clone(&toy));
+let borrowed = kennel.get(&ball).unwrap();
+drop(ball);
+assert_eq!(Arc::strong_count(&toy), 2);
+drop(borrowed);
+assert_eq!(Arc::strong_count(&toy), 1);
+```
+The locks are not reentrant. Taking the lock of a type again on a thread that holds it through a
+borrow would wait forever, so it panics instead. Only shared borrows can be taken again, as long as
+no other thread is waiting to borrow an entity of the type mutably
+```rust
+use arena_allocator::SyncArena;
+use std::panic::{self, AssertUnwindSafe};
+
+let arena = SyncArena::default();
+let bruce = arena.allocate(format!("Bruce"));
+let rex = arena.allocate(format!("Rex"));
+let borrowed = arena.get(&bruce).unwrap();
+assert!(panic::catch_unwind(AssertUnwindSafe(|| arena.allocate(format!("Max")))).is_err());
+assert!(panic::catch_unwind(AssertUnwindSafe(|| arena.get_mut(&rex).is_some())).is_err());
+assert_eq!(*arena.get(&rex).unwrap(), "Rex");
+drop(borrowed);
+
+let mut borrowed = arena.get_mut(&bruce).unwrap();
+assert!(panic::catch_unwind(AssertUnwindSafe(|| arena.get(&rex).is_some())).is_err());
+borrowed.push_str(" the second");
+drop(borrowed);
+assert_eq!(*bruce.get().unwrap(), "Bruce the second");
+```
+
+### Stress tests
+
+Many threads allocating and freeing the same type at once
+```rust
+use arena_allocator::{SyncAddress, SyncArena};
+use std::thread;
+
+let arena = SyncArena::new(4);
+let kept: Vec<Vec<SyncAddress<usize>>> = thread::scope(|scope| {
+    let workers: Vec<_> = (0..8)
+        .map(|worker| {
+            let arena = &arena;
+            scope.spawn(move || {
+                let mut kept = Vec::new();
+                for i in 0..1_000 {
+                    let value = worker * 1_000 + i;
+                    let address = arena.allocate(value);
+                    assert_eq!(*address.get().unwrap(), value);
+                    if i % 2 == 0 {
+                        kept.push(address);
+                    } else {
+                        *arena.get_mut(&address).unwrap() += 1;
+                        assert_eq!(*arena.get(&address).unwrap(), value + 1);
+                        arena.free(&address);
+                        assert_eq!(address.get().is_none(), true);
+                    }
+                }
+                kept
+            })
+        })
+        .collect();
+    workers.into_iter().map(|w| w.join().unwrap()).collect()
+});
+for (worker, addresses) in kept.iter().enumerate() {
+    for (i, address) in addresses.iter().enumerate() {
+        assert_eq!(*address.get().unwrap(), worker * 1_000 + i * 2);
+    }
+}
+```
+Many threads copying and dropping addresses of the same entity, which is freed exactly once when
+the last address is dropped
+```rust
+use arena_allocator::{SyncAddress, SyncArena};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+
+struct Counted(Arc<AtomicUsize>);
+
+impl Drop for Counted {
+    fn drop(&mut self) {
+        self.0.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+let drops = Arc::new(AtomicUsize::new(0));
+let arena = SyncArena::default();
+let address = arena.allocate(Counted(Arc::clone(&drops)));
+thread::scope(|scope| {
+    for _ in 0..8 {
+        let address = address.clone();
+        scope.spawn(move || {
+            for _ in 0..1_000 {
+                let copy = address.clone();
+                assert_eq!(copy.get().is_some(), true);
+            }
+        });
+    }
+});
+assert_eq!(drops.load(Ordering::SeqCst), 0);
+drop(address);
+assert_eq!(drops.load(Ordering::SeqCst), 1);
+```
+ */
+
+#![forbid(missing_docs, missing_debug_implementations)]
+
+use std::any::{Any, TypeId};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::mem::{self, ManuallyDrop};
+use std::ops::{Deref, DerefMut};
+use std::sync::atomic::{AtomicIsize, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, RwLock, RwLockReadGuard, RwLockWriteGuard, TryLockError, Weak};
+
+static DEFAULT_CAPACITY: usize = 16;
+
+thread_local! {
+    /// The groups whose lock the current thread holds through a borrow guard, by their address.
+    /// Holds the number of shared guards, or -1 for a mutable one, like the flag of a RefCell
+    static HELD: RefCell<HashMap<usize, isize>> = RefCell::new(HashMap::new());
+}
+
+/// Thread safe version of `Arena`. Holds the collection of objects, with a lock for every type
+pub struct SyncArena {
+    data: RwLock<HashMap<TypeId, Arc<dyn Any + Send + Sync>>>,
+    capacity: usize,
+}
+
+/// Thread safe version of `LocationGroup`, the locations of a type along with the head of the list
+/// of vacant locations, all behind a single lock
+///
+/// Dropping an address must not wait for the lock, which the same thread may be holding through a
+/// borrow. When the lock is taken, the release is put in `deferred` instead, and carried out the
+/// next time the lock is released or taken for writing
+struct SyncLocationGroup<T> {
+    locations: RwLock<SyncLocations<T>>,
+    deferred: Mutex<Vec<Deferred>>,
+    this: Weak<SyncLocationGroup<T>>,
+}
+
+/// Work on the reference count of a location that could not take the lock of its group
+enum Deferred {
+    /// An address was dropped, the count must go down and the entity freed if it was the last one
+    Release { index: usize, generation: usize },
+    /// The last address was dropped, the entity must be freed
+    Free { index: usize, generation: usize },
+}
+
+/// Everything guarded by the lock of a group
+struct SyncLocations<T> {
+    list: Vec<SyncLocation<T>>,
+    first_free: Option<usize>,
+}
+
+/// Thread safe version of `Location`. The generation and the reference count are atomics, so they
+/// can be read and updated while only holding the read lock of the group
+struct SyncLocation<T> {
+    generation: AtomicUsize,
+    ref_count: AtomicIsize,
+    slot: SyncSlot<T>,
+}
+
+/// What a location currently holds. A vacant slot holds the index of the next vacant location,
+/// if any
+enum SyncSlot<T> {
+    Occupied(T),
+    Vacant { next_free: Option<usize> },
+}
+
+/// Thread safe version of `Address`. It can be sent to and shared with other threads as long as
+/// the entity itself can
+pub struct SyncAddress<T: 'static> {
+    /// Generation of the address, for an Address to be not None, generation must be the same as
+    /// the generation in the target location
+    pub generation: usize,
+    /// Index of the entities in the array
+    pub index: usize,
+    group: Weak<SyncLocationGroup<T>>,
+}
+
+/// Guard for a shared borrow of an entity in a `SyncArena`. Holds the read lock of the entity's
+/// type, so other threads can read entities of that type but not change them until it is dropped
+pub struct SyncRef<'a, T> {
+    // released by hand when dropped, before the group
+    guard: ManuallyDrop<RwLockReadGuard<'a, SyncLocations<T>>>,
+    index: usize,
+    group: Arc<SyncLocationGroup<T>>,
+}
+
+/// Guard for a mutable borrow of an entity in a `SyncArena`. Holds the write lock of the entity's
+/// type, so no other thread can access entities of that type until it is dropped
+pub struct SyncRefMut<'a, T> {
+    // released by hand when dropped, before the group
+    guard: ManuallyDrop<RwLockWriteGuard<'a, SyncLocations<T>>>,
+    index: usize,
+    group: Arc<SyncLocationGroup<T>>,
+}
+
+impl<T> SyncLocations<T> {
+    /// Whether the location at an index holds a live entity of the given generation
+    fn is_live(&self, index: usize, generation: usize) -> bool {
+        self.list.get(index).is_some_and(|location| {
+            location.generation.load(Ordering::Acquire) == generation
+                && matches!(location.slot, SyncSlot::Occupied(_))
+        })
+    }
+
+    /// Mark the location at an index as free, if it is still of the given generation, and hand
+    /// back the entity so it can be dropped once the lock is released
+    fn vacate(&mut self, index: usize, generation: usize) -> Option<SyncSlot<T>> {
+        if !self.is_live(index, generation) {
+            return None;
+        }
+        let next_free = self.first_free;
+        self.first_free = Some(index);
+        let location = &mut self.list[index];
+        location.generation.fetch_add(1, Ordering::AcqRel);
+        Some(mem::replace(
+            &mut location.slot,
+            SyncSlot::Vacant { next_free },
+        ))
+    }
+}
+
+impl<T> SyncLocationGroup<T> {
+    fn new(capacity: usize) -> Arc<SyncLocationGroup<T>> {
+        Arc::new_cyclic(|this| SyncLocationGroup {
+            locations: RwLock::new(SyncLocations {
+                list: Vec::with_capacity(capacity),
+                first_free: None,
+            }),
+            deferred: Mutex::new(Vec::new()),
+            this: Weak::clone(this),
+        })
+    }
+
+    /// Takes the read lock, in order to do the given action
+    ///
+    /// Panics instead of waiting forever if the current thread mutably borrows an entity of the
+    /// group, or if it borrows one while another thread waits for the write lock
+    fn read(&self, action: &str) -> RwLockReadGuard<'_, SyncLocations<T>> {
+        match self.held() {
+            0 => self.locations.read().unwrap_or_else(|e| e.into_inner()),
+            -1 => panic!(
+                "can not {} while an entity of the same type is mutably borrowed on this thread",
+                action
+            ),
+            _ => match self.locations.try_read() {
+                Ok(locations) => locations,
+                Err(TryLockError::Poisoned(e)) => e.into_inner(),
+                Err(TryLockError::WouldBlock) => panic!(
+                    "can not {} while an entity of the same type is borrowed on this thread and \
+                     another thread waits to borrow one mutably",
+                    action
+                ),
+            },
+        }
+    }
+
+    /// Takes the write lock, in order to do the given action
+    ///
+    /// Panics instead of waiting forever if the current thread borrows an entity of the group
+    fn write(&self, action: &str) -> RwLockWriteGuard<'_, SyncLocations<T>> {
+        if self.held() != 0 {
+            panic!(
+                "can not {} while an entity of the same type is borrowed on this thread",
+                action
+            );
+        }
+        self.locations.write().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// How the current thread holds the lock through borrow guards, see `HELD`
+    fn held(&self) -> isize {
+        HELD.with(|held| held.borrow().get(&self.key()).copied().unwrap_or(0))
+    }
+
+    /// Records that the current thread took a borrow guard, or released one when `held` is
+    /// false. Shared guards are counted, there is only ever one mutable guard
+    fn hold(&self, mutable: bool, held: bool) {
+        HELD.with(|flags| {
+            let mut flags = flags.borrow_mut();
+            let flag = flags.entry(self.key()).or_insert(0);
+            *flag = match (mutable, held) {
+                (true, true) => -1,
+                (true, false) => 0,
+                (false, true) => *flag + 1,
+                (false, false) => *flag - 1,
+            };
+            if *flag == 0 {
+                flags.remove(&self.key());
+            }
+        })
+    }
+
+    /// Tells the group apart from others in `HELD`
+    fn key(&self) -> usize {
+        self as *const Self as usize
+    }
+
+    /// Carries out the deferred releases while holding the write lock. The freed entities are
+    /// handed back, to be dropped once the lock is released
+    fn apply_deferred(&self, locations: &mut SyncLocations<T>) -> Vec<SyncSlot<T>> {
+        let deferred = mem::take(&mut *self.deferred.lock().unwrap_or_else(|e| e.into_inner()));
+        let mut freed = Vec::new();
+        for work in deferred {
+            let (index, generation) = match work {
+                Deferred::Release { index, generation } => {
+                    if !locations.is_live(index, generation)
+                        || locations.list[index]
+                            .ref_count
+                            .fetch_sub(1, Ordering::AcqRel)
+                            != 1
+                    {
+                        continue;
+                    }
+                    (index, generation)
+                }
+                Deferred::Free { index, generation } => (index, generation),
+            };
+            freed.extend(locations.vacate(index, generation));
+        }
+        freed
+    }
+
+    /// Carries out the deferred releases, unless the lock is taken right now. In that case they
+    /// are left for whoever holds it
+    fn flush(&self) {
+        if self
+            .deferred
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .is_empty()
+        {
+            return;
+        }
+        let freed = match self.locations.try_write() {
+            Ok(mut locations) => self.apply_deferred(&mut locations),
+            Err(TryLockError::Poisoned(e)) => self.apply_deferred(&mut e.into_inner()),
+            Err(TryLockError::WouldBlock) => return,
+        };
+        drop(freed);
+    }
+
+    /// Puts off work on a location until the lock can be taken
+    fn defer(&self, work: Deferred) {
+        self.deferred
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(work);
+        // the lock may have been released in the meantime, with nothing left to flush
+        self.flush();
+    }
+
+    fn allocate(&self, v: T) -> SyncAddress<T> {
+        let mut locations = self.write("allocate an entity");
+        let freed = self.apply_deferred(&mut locations);
+        let (generation, index): (usize, usize);
+        match locations.first_free {
+            Some(idx) => {
+                let location = &mut locations.list[idx];
+                let slot = mem::replace(&mut location.slot, SyncSlot::Occupied(v));
+                *location.ref_count.get_mut() = 1;
+                generation = *location.generation.get_mut();
+                index = idx;
+                if let SyncSlot::Vacant { next_free } = slot {
+                    locations.first_free = next_free;
+                }
+            }
+            None => {
+                generation = 0;
+                index = locations.list.len();
+                locations.list.push(SyncLocation {
+                    generation: AtomicUsize::new(generation),
+                    ref_count: AtomicIsize::new(1),
+                    slot: SyncSlot::Occupied(v),
+                });
+            }
+        }
+        drop(locations);
+        drop(freed);
+        SyncAddress {
+            generation,
+            index,
+            group: Weak::clone(&self.this),
+        }
+    }
+
+    /// Borrow the entity at an index, if it is still of the given generation.
+    /// The guard keeps the group alive, so it is free to outlive both the arena and the address
+    /// it was borrowed through
+    fn borrow<'a>(self: Arc<Self>, index: usize, generation: usize) -> Option<SyncRef<'a, T>> {
+        // SAFETY: the group is kept alive by the Arc moved into the guard, which releases the lock
+        // before the Arc
+        let group: &'a SyncLocationGroup<T> = unsafe { &*Arc::as_ptr(&self) };
+        let guard = group.read("borrow an entity");
+        if !guard.is_live(index, generation) {
+            return None;
+        }
+        group.hold(false, true);
+        Some(SyncRef {
+            guard: ManuallyDrop::new(guard),
+            index,
+            group: self,
+        })
+    }
+
+    /// Mutably borrow the entity at an index, if it is still of the given generation.
+    /// The guard keeps the group alive, so it is free to outlive both the arena and the address
+    /// it was borrowed through
+    fn borrow_mut<'a>(
+        self: Arc<Self>,
+        index: usize,
+        generation: usize,
+    ) -> Option<SyncRefMut<'a, T>> {
+        // SAFETY: the group is kept alive by the Arc moved into the guard, which releases the lock
+        // before the Arc
+        let group: &'a SyncLocationGroup<T> = unsafe { &*Arc::as_ptr(&self) };
+        let guard = group.write("mutably borrow an entity");
+        if !guard.is_live(index, generation) {
+            return None;
+        }
+        group.hold(true, true);
+        Some(SyncRefMut {
+            guard: ManuallyDrop::new(guard),
+            index,
+            group: self,
+        })
+    }
+
+    /// Adds a reference to the entity at an index, if it is still of the given generation
+    fn retain(&self, index: usize, generation: usize) {
+        let locations = self.read("copy an address");
+        if locations.is_live(index, generation) {
+            locations.list[index]
+                .ref_count
+                .fetch_add(1, Ordering::AcqRel);
+        }
+    }
+
+    /// Drops a reference to the entity at an index, if it is still of the given generation, and
+    /// frees the entity when it was the last one. Never waits for the lock, the work is deferred
+    /// if the lock is taken
+    fn release(&self, index: usize, generation: usize) {
+        let last = {
+            let locations = match self.locations.try_read() {
+                Ok(locations) => locations,
+                Err(TryLockError::Poisoned(e)) => e.into_inner(),
+                Err(TryLockError::WouldBlock) => {
+                    return self.defer(Deferred::Release { index, generation });
+                }
+            };
+            locations.is_live(index, generation)
+                && locations.list[index]
+                    .ref_count
+                    .fetch_sub(1, Ordering::AcqRel)
+                    == 1
+        };
+        if !last {
+            return;
+        }
+        let freed = match self.locations.try_write() {
+            Ok(mut locations) => locations.vacate(index, generation),
+            Err(TryLockError::Poisoned(e)) => e.into_inner().vacate(index, generation),
+            Err(TryLockError::WouldBlock) => {
+                return self.defer(Deferred::Free { index, generation });
+            }
+        };
+        drop(freed);
+    }
+
+    /// Mark the location at an index as free, if it is still of the given generation. The entity
+    /// is dropped right away, after the lock has been released
+    fn free(&self, index: usize, generation: usize) {
+        let freed = {
+            let mut locations = self.write("free an entity");
+            let mut freed = self.apply_deferred(&mut locations);
+            freed.extend(locations.vacate(index, generation));
+            freed
+        };
+        // the entity's destructor may drop addresses of this same type, which need the lock
+        drop(freed);
+    }
+}
+
+impl SyncArena {
+    /// Creates a new arena with a given capacity.
+    /// The capacity dictates the initial size of all arrays created for each entity
+    pub fn new(capacity: usize) -> SyncArena {
+        SyncArena {
+            data: RwLock::new(HashMap::new()),
+            capacity,
+        }
+    }
+
+    /// The group holding entities of a type, if any was allocated
+    fn group<T: Send + Sync + 'static>(&self) -> Option<Arc<SyncLocationGroup<T>>> {
+        let data = self.data.read().unwrap_or_else(|e| e.into_inner());
+        let group = Arc::clone(data.get(&TypeId::of::<T>())?);
+        group.downcast::<SyncLocationGroup<T>>().ok()
+    }
+
+    /// The group holding entities of a type, if the address was allocated in it
+    fn group_of<T: Send + Sync + 'static>(
+        &self,
+        address: &SyncAddress<T>,
+    ) -> Option<Arc<SyncLocationGroup<T>>> {
+        self.group::<T>().filter(|group| address.belongs_to(group))
+    }
+
+    /// Borrow the entity at a given address. None means the entity was freed, or the address
+    /// belongs to another arena.
+    /// Blocks while another thread mutably borrows an entity of the same type
+    ///
+    /// Panics if this thread mutably borrows an entity of the same type, or if it borrows one while
+    /// another thread waits to borrow one mutably
+    pub fn get<T: Send + Sync + 'static>(
+        &self,
+        address: &SyncAddress<T>,
+    ) -> Option<SyncRef<'_, T>> {
+        self.group_of(address)?
+            .borrow(address.index, address.generation)
+    }
+
+    /// Mutably borrow the entity at a given address. None means the entity was freed, or the
+    /// address belongs to another arena.
+    /// Blocks while another thread borrows an entity of the same type
+    ///
+    /// Panics if this thread borrows an entity of the same type
+    pub fn get_mut<T: Send + Sync + 'static>(
+        &self,
+        address: &SyncAddress<T>,
+    ) -> Option<SyncRefMut<'_, T>> {
+        self.group_of(address)?
+            .borrow_mut(address.index, address.generation)
+    }
+
+    /// Adds a new entity to the arena and returns the address to that entity.
+    /// Blocks while another thread borrows an entity of the same type
+    ///
+    /// Panics if this thread borrows an entity of the same type
+    pub fn allocate<T: Send + Sync + 'static>(&self, v: T) -> SyncAddress<T> {
+        let group = match self.group::<T>() {
+            Some(group) => group,
+            None => {
+                let mut data = self.data.write().unwrap_or_else(|e| e.into_inner());
+                let group = data
+                    .entry(TypeId::of::<T>())
+                    .or_insert_with(|| SyncLocationGroup::<T>::new(self.capacity));
+                Arc::clone(group)
+                    .downcast::<SyncLocationGroup<T>>()
+                    .unwrap()
+            }
+        };
+        group.allocate(v)
+    }
+
+    /// Mark the location of the address as free. This opens up that location and all remaining
+    /// references will no longer be valid. The entity is dropped right away. Does nothing if the
+    /// address belongs to another arena
+    ///
+    /// Blocks while another thread borrows an entity of the same type
+    ///
+    /// Panics if this thread borrows an entity of the same type
+    pub fn free<T: Send + Sync + 'static>(&self, address: &SyncAddress<T>) {
+        if let Some(group) = self.group_of(address) {
+            group.free(address.index, address.generation)
+        }
+    }
+}
+
+impl<T> SyncAddress<T> {
+    /// Borrow the entity the address is pointing to. None means the entity was freed by something
+    /// else, or the arena was dropped. Panics like `SyncArena::get()`
+    pub fn get(&self) -> Option<SyncRef<'_, T>> {
+        self.group.upgrade()?.borrow(self.index, self.generation)
+    }
+
+    /// Mutably borrow the entity the address is pointing to. None means the entity was freed by
+    /// something else, or the arena was dropped. Panics like `SyncArena::get_mut()`
+    pub fn get_mut(&self) -> Option<SyncRefMut<'_, T>> {
+        self.group
+            .upgrade()?
+            .borrow_mut(self.index, self.generation)
+    }
+
+    /// Get a copy of the Address without taking ownership
+    ///
+    /// Panics if this thread mutably borrows an entity of the same type, see `SyncArena::get()`
+    pub fn copy(&self) -> SyncAddress<T> {
+        if let Some(group) = self.group.upgrade() {
+            group.retain(self.index, self.generation);
+        }
+        SyncAddress {
+            generation: self.generation,
+            index: self.index,
+            group: Weak::clone(&self.group),
+        }
+    }
+
+    /// Force freeing of an entity regardless of their reference count
+    ///
+    /// Panics if this thread borrows an entity of the same type, see `SyncArena::free()`
+    pub fn remove(&self) {
+        if let Some(group) = self.group.upgrade() {
+            group.free(self.index, self.generation)
+        }
+    }
+
+    /// Whether the address was allocated in the given group
+    fn belongs_to(&self, group: &Arc<SyncLocationGroup<T>>) -> bool {
+        Weak::ptr_eq(&self.group, &Arc::downgrade(group))
+    }
+}
+
+impl<T> Clone for SyncAddress<T> {
+    /// Same as `copy()`, the clone is counted as another reference to the entity
+    fn clone(&self) -> Self {
+        self.copy()
+    }
+}
+
+impl<T> Drop for SyncAddress<T> {
+    /// Frees the entity once the last address to it is dropped. Dropping an address after its
+    /// arena was dropped does nothing
+    ///
+    /// Never waits for the lock of the type. If an entity of the type is borrowed at the time,
+    /// the entity is freed once the borrow ends
+    fn drop(&mut self) {
+        if let Some(group) = self.group.upgrade() {
+            group.release(self.index, self.generation)
+        }
+    }
+}
+
+impl<T> Drop for SyncRef<'_, T> {
+    /// Releases the lock, then carries out the releases deferred while it was held
+    fn drop(&mut self) {
+        // SAFETY: the guard is not used again
+        unsafe { ManuallyDrop::drop(&mut self.guard) };
+        self.group.hold(false, false);
+        self.group.flush();
+    }
+}
+
+impl<T> Drop for SyncRefMut<'_, T> {
+    /// Releases the lock, then carries out the releases deferred while it was held
+    fn drop(&mut self) {
+        // SAFETY: the guard is not used again
+        unsafe { ManuallyDrop::drop(&mut self.guard) };
+        self.group.hold(true, false);
+        self.group.flush();
+    }
+}
+
+impl<T> Deref for SyncRef<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        match &self.guard.list[self.index].slot {
+            SyncSlot::Occupied(entity) => entity,
+            SyncSlot::Vacant { .. } => unreachable!("borrowed entities can not be freed"),
+        }
+    }
+}
+
+impl<T> Deref for SyncRefMut<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        match &self.guard.list[self.index].slot {
+            SyncSlot::Occupied(entity) => entity,
+            SyncSlot::Vacant { .. } => unreachable!("borrowed entities can not be freed"),
+        }
+    }
+}
+
+impl<T> DerefMut for SyncRefMut<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        match &mut self.guard.list[self.index].slot {
+            SyncSlot::Occupied(entity) => entity,
+            SyncSlot::Vacant { .. } => unreachable!("borrowed entities can not be freed"),
+        }
+    }
+}
+
+impl Default for SyncArena {
+    fn default() -> Self {
+        SyncArena::new(DEFAULT_CAPACITY)
+    }
+}
+
+impl fmt::Debug for SyncArena {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SyncArena")
+            .field("capacity", &self.capacity)
+            .finish()
+    }
+}
+
+impl<T> fmt::Debug for SyncAddress<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SyncAddress")
+            .field("generation", &self.generation)
+            .field("index", &self.index)
+            .finish()
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for SyncRef<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        (**self).fmt(f)
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for SyncRefMut<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        (**self).fmt(f)
+    }
+}
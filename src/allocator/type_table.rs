@@ -0,0 +1,103 @@
+/*!
+This module implements the table an arena keeps the list of entities of each type in.
+
+Types are numbered in the order their first entity was allocated, and the lists are kept in a
+plain `Vec` at that number. Finding the number of a type is a single lookup keyed by its
+`TypeId`, which is already a hash and is used as is. Going over every list, for statistics or
+clearing, walks the `Vec` without knowing any of the types
+ */
+
+#![forbid(missing_docs, missing_debug_implementations)]
+
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::{BuildHasherDefault, Hasher};
+use std::rc::Rc;
+
+use super::arena::{AnyGroup, LocationGroup};
+
+/// The group of every type in an arena, in the order the types were added
+pub(crate) struct TypeTable {
+    /// Position of every type in the lists below
+    indexes: HashMap<TypeId, usize, BuildHasherDefault<TypeIdHasher>>,
+    /// The `Rc<LocationGroup<T>>` of every type, for getting it back with its type
+    typed: Vec<Box<dyn Any>>,
+    /// The same groups, for going over all of them without knowing their types
+    erased: Vec<Rc<dyn AnyGroup>>,
+}
+
+/// Hasher for `TypeId`s, which hash themselves as a single `u64` that is already well spread
+#[derive(Default)]
+struct TypeIdHasher(u64);
+
+impl TypeTable {
+    pub(crate) fn new() -> TypeTable {
+        TypeTable {
+            indexes: HashMap::default(),
+            typed: Vec::new(),
+            erased: Vec::new(),
+        }
+    }
+
+    /// The group of a type, if it was added
+    #[inline]
+    pub(crate) fn get<T: 'static>(&self) -> Option<&Rc<LocationGroup<T>>> {
+        let index = *self.indexes.get(&TypeId::of::<T>())?;
+        self.typed[index].downcast_ref()
+    }
+
+    /// The group of a type, if it was added, without its type
+    pub(crate) fn get_any(&self, type_id: TypeId) -> Option<&Rc<dyn AnyGroup>> {
+        Some(&self.erased[*self.indexes.get(&type_id)?])
+    }
+
+    /// Adds the group of a type, which must not have one yet
+    pub(crate) fn insert<T: 'static>(&mut self, group: Rc<LocationGroup<T>>) {
+        let index = self.typed.len();
+        let previous = self.indexes.insert(TypeId::of::<T>(), index);
+        debug_assert!(previous.is_none(), "the type already has a group");
+        self.erased.push(Rc::clone(&group) as Rc<dyn AnyGroup>);
+        self.typed.push(Box::new(group));
+    }
+
+    /// Removes the group of a type, the types added after it move down by one
+    pub(crate) fn remove<T: 'static>(&mut self) -> Option<Rc<LocationGroup<T>>> {
+        let index = self.indexes.remove(&TypeId::of::<T>())?;
+        self.erased.remove(index);
+        for later in self.indexes.values_mut() {
+            if *later > index {
+                *later -= 1;
+            }
+        }
+        self.typed.remove(index).downcast().ok().map(|group| *group)
+    }
+
+    /// Every group, in the order they were added
+    pub(crate) fn iter(&self) -> impl Iterator<Item = &Rc<dyn AnyGroup>> {
+        self.erased.iter()
+    }
+}
+
+impl Hasher for TypeIdHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        // only used if `TypeId` ever hashes itself as something else than a `u64`
+        for &byte in bytes {
+            self.0 = self.0.rotate_left(8) ^ u64::from(byte);
+        }
+    }
+
+    fn write_u64(&mut self, value: u64) {
+        self.0 = value;
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+impl fmt::Debug for TypeTable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.erased.iter()).finish()
+    }
+}
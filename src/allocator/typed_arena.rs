@@ -0,0 +1,312 @@
+/*!
+This module implements the typed arena, an arena for entities of a single type.
+
+`TypedArena<T>` owns the list of entities of its type directly, so no type is looked up on any
+access. It hands out the same addresses as `Arena` and has the same methods, without the type
+parameter on each of them, and is a plain value that can be kept as a field of a struct
+```rust
+use arena_allocator::{Address, TypedArena};
+
+struct Particle {x: f32, speed: f32}
+
+struct Particles {
+    pool: TypedArena<Particle>,
+    emitted: Vec<Address<Particle>>,
+}
+
+let mut particles = Particles {pool: TypedArena::new(64), emitted: Vec::new()};
+for i in 0..4 {
+    let particle = particles.pool.allocate(Particle{x: 0.0, speed: i as f32});
+    particles.emitted.push(particle);
+}
+for (_, mut particle) in &mut particles.pool {
+    particle.x += particle.speed;
+}
+particles.emitted.remove(0);
+assert_eq!(particles.pool.get(&particles.emitted[2]).unwrap().x, 3.0);
+assert_eq!((&particles.pool).into_iter().count(), 3);
+```
+There is no indexing with `pool[&address]` on purpose. `Index` would have to hand out a plain
+`&T`, which the borrow flag of the entity does not track, so `Address::get_mut()` could alias it.
+Use `get()` and `get_mut()`, whose guards are tracked
+The arena works as a collection of its entities as well. Entities added by `extend()` or
+`collect()` are owned by the arena itself rather than by an address, and live until they are freed
+or moved out of the arena by `into_iter()`
+```rust
+use arena_allocator::TypedArena;
+
+let mut numbers: TypedArena<i32> = (1..=3).collect();
+numbers.extend(vec![4, 5]);
+let (address, _) = numbers.iter().next().unwrap();
+drop(address);
+numbers.retain(|_, n| *n % 2 == 1);
+assert_eq!(numbers.into_iter().collect::<Vec<_>>(), vec![1, 3, 5]);
+```
+ */
+
+#![forbid(missing_docs, missing_debug_implementations)]
+
+use std::fmt;
+use std::iter::FromIterator;
+use std::rc::Rc;
+
+use super::address::Address;
+use super::arena::{AnyGroup, ArenaId, Iter, IterMut, LocationGroup};
+use super::borrow::{BorrowError, Ref, RefMut};
+use super::compact::Remap;
+use super::config::ArenaConfig;
+use super::error::ArenaError;
+use super::handle::{Handle, Locator};
+use super::stats::TypeStats;
+
+/// An arena holding entities of a single type
+pub struct TypedArena<T: 'static> {
+    group: Rc<LocationGroup<T>>,
+    id: ArenaId,
+}
+
+/// Iterator moving every live entity out of a typed arena, created by `into_iter()`
+pub struct IntoIter<T: 'static> {
+    group: Rc<LocationGroup<T>>,
+    next: usize,
+}
+
+impl<T: 'static> TypedArena<T> {
+    /// Creates a new arena with room for `capacity` entities
+    pub fn new(capacity: usize) -> TypedArena<T> {
+        TypedArena::with_config(ArenaConfig {
+            capacity,
+            ..ArenaConfig::default()
+        })
+    }
+
+    /// Creates a new arena with the given settings
+    ///
+    /// Panics if the settings are not valid, see `Arena::with_config()`
+    pub fn with_config(config: ArenaConfig) -> TypedArena<T> {
+        config.check();
+        let id = ArenaId::next();
+        TypedArena {
+            group: LocationGroup::new(&config, config.capacity, id),
+            id,
+        }
+    }
+
+    /// The id of the arena, carried by every address allocated in it
+    pub fn id(&self) -> ArenaId {
+        self.id
+    }
+
+    /// Borrow the entity at a given address or handle, see `Arena::get()`
+    #[inline]
+    pub fn get(&self, address: impl Locator<T>) -> Option<Ref<'_, T>> {
+        ArenaError::found(self.try_get(address))
+    }
+
+    /// Mutably borrow the entity at a given address or handle, see `Arena::get_mut()`
+    #[inline]
+    pub fn get_mut(&mut self, address: impl Locator<T>) -> Option<RefMut<'_, T>> {
+        ArenaError::found(self.try_get_mut(address))
+    }
+
+    /// Borrow the entity at a given address or handle, see `Arena::try_get()`
+    pub fn try_get(&self, address: impl Locator<T>) -> Result<Ref<'_, T>, ArenaError> {
+        let (index, generation) = self.group.locate(&address)?;
+        Rc::clone(&self.group)
+            .borrow(index, generation)
+            .map_err(|error| ArenaError::from_borrow(error, index, generation))
+    }
+
+    /// Mutably borrow the entity at a given address or handle, see `Arena::try_get_mut()`
+    pub fn try_get_mut(&mut self, address: impl Locator<T>) -> Result<RefMut<'_, T>, ArenaError> {
+        let (index, generation) = self.group.locate(&address)?;
+        Rc::clone(&self.group)
+            .borrow_mut(index, generation)
+            .map_err(|error| ArenaError::from_borrow(error, index, generation))
+    }
+
+    /// Get a new address to the entity at a given handle, see `Arena::address()`
+    pub fn address(&self, handle: Handle<T>) -> Option<Address<T>> {
+        let (index, generation) = self.group.locate(&handle).ok()?;
+        self.group.address(index, generation)
+    }
+
+    /// Adds a new entity and returns the address to that entity, see `Arena::allocate()`
+    #[inline]
+    pub fn allocate(&mut self, v: T) -> Address<T> {
+        // SAFETY: the arena is held mutably
+        unsafe { self.group.allocate(v) }
+    }
+
+    /// Adds a new entity, or gives it back if there is no room left, see `Arena::try_allocate()`
+    pub fn try_allocate(&mut self, v: T) -> Result<Address<T>, T> {
+        // SAFETY: the arena is held mutably
+        unsafe { self.group.try_allocate(v) }
+    }
+
+    /// Adds every entity of an iterator, see `Arena::allocate_many()`
+    pub fn allocate_many<I: IntoIterator<Item = T>>(&mut self, values: I) -> Vec<Address<T>> {
+        // SAFETY: the arena is held mutably
+        unsafe { self.group.allocate_many(values) }
+    }
+
+    /// Adds `n` entities made by `f`, see `Arena::allocate_from_fn()`
+    pub fn allocate_from_fn(&mut self, n: usize, f: impl FnMut(usize) -> T) -> Vec<Address<T>> {
+        self.allocate_many((0..n).map(f))
+    }
+
+    /// Free the entity at a given address or handle, see `Arena::free()`
+    #[inline]
+    pub fn free(&self, address: impl Locator<T>) {
+        ArenaError::found(self.try_free(address));
+    }
+
+    /// Free the entity at a given address or handle, see `Arena::try_free()`
+    pub fn try_free(&self, address: impl Locator<T>) -> Result<(), ArenaError> {
+        let (index, generation) = self.group.locate(&address)?;
+        self.group
+            .try_free(index, generation)
+            .map_err(|error| ArenaError::from_borrow(error, index, generation))
+    }
+
+    /// Moves every entity to the front of the list, see `Arena::compact()`
+    pub fn compact(&mut self) -> Remap<T> {
+        // SAFETY: the arena is held mutably
+        Remap::new(unsafe { self.group.compact() })
+    }
+
+    /// Frees the entities `keep` returns false for, see `Arena::retain()`
+    pub fn retain(&mut self, mut keep: impl FnMut(&Address<T>, &mut T) -> bool) -> usize {
+        let mut removed = 0;
+        let group = Rc::clone(&self.group);
+        for (address, mut entity) in self.iter_mut() {
+            if !keep(&address, &mut entity) {
+                // the borrow has to end before the entity can be freed
+                drop(entity);
+                group.free(address.index, address.generation);
+                removed += 1;
+            }
+        }
+        removed
+    }
+
+    /// Frees every entity at once, see `Arena::clear()`
+    pub fn clear(&mut self) {
+        AnyGroup::clear(&*self.group)
+    }
+
+    /// Statistics of the entities in the arena, see `Arena::stats()`
+    pub fn stats(&self) -> TypeStats {
+        self.group.stats()
+    }
+
+    /// Iterate over every live entity, see `Arena::iter()`
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter::new(Some(Rc::clone(&self.group)))
+    }
+
+    /// Iterate over every live entity with mutable access, see `Arena::iter_mut()`
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        IterMut::new(Some(Rc::clone(&self.group)))
+    }
+}
+
+impl<T: 'static> Iterator for IntoIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        while self.next < self.group.locations().len() {
+            let index = self.next;
+            self.next += 1;
+            let generation = self.group.locations()[index].generation.get();
+            match self.group.take(index, generation) {
+                Ok(entity) => return Some(entity),
+                Err(BorrowError::AlreadyBorrowed) => {
+                    panic!("can not move an entity out of the arena while it is borrowed")
+                }
+                Err(BorrowError::Freed) => {}
+            }
+        }
+        None
+    }
+}
+
+/// Moves every live entity out of the arena, in the order they are stored. Outstanding
+/// addresses stop resolving
+///
+/// Panics when reaching an entity that is borrowed
+impl<T: 'static> IntoIterator for TypedArena<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(self) -> IntoIter<T> {
+        IntoIter {
+            group: self.group,
+            next: 0,
+        }
+    }
+}
+
+impl<'a, T: 'static> IntoIterator for &'a TypedArena<T> {
+    type Item = (Address<T>, Ref<'a, T>);
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+impl<'a, T: 'static> IntoIterator for &'a mut TypedArena<T> {
+    type Item = (Address<T>, RefMut<'a, T>);
+    type IntoIter = IterMut<'a, T>;
+
+    fn into_iter(self) -> IterMut<'a, T> {
+        self.iter_mut()
+    }
+}
+
+/// Adds entities owned by the arena itself, which count as one owner of each of them. They are
+/// kept alive until freed, through an address from `iter()` for instance
+///
+/// Panics if the arena is not allowed to grow and runs out of room
+impl<T: 'static> Extend<T> for TypedArena<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, values: I) {
+        for v in values {
+            // SAFETY: the arena is held mutably
+            if unsafe { self.group.try_insert(v) }.is_err() {
+                panic!("the arena is full and not allowed to grow");
+            }
+        }
+    }
+}
+
+impl<T: 'static> FromIterator<T> for TypedArena<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(values: I) -> Self {
+        let mut arena = TypedArena::default();
+        arena.extend(values);
+        arena
+    }
+}
+
+impl<T: 'static> Default for TypedArena<T> {
+    fn default() -> Self {
+        TypedArena::with_config(ArenaConfig::default())
+    }
+}
+
+impl<T: 'static> fmt::Debug for TypedArena<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TypedArena")
+            .field("id", &self.id)
+            .field("stats", &self.stats())
+            .finish()
+    }
+}
+
+impl<T> fmt::Debug for IntoIter<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("IntoIter")
+            .field("next", &self.next)
+            .finish()
+    }
+}
@@ -0,0 +1,152 @@
+/*!
+This module implements views of a single type in an arena, created by `Arena::view()`.
+
+Every access through the arena first finds the list of the entity's type in the table of types.
+A view finds the list once, and every access through it goes straight to the location at the
+index of the address. It holds the arena mutably for as long as it lives, so it can allocate as
+well
+```rust
+use arena_allocator::{Arena, Handle};
+let mut arena = Arena::default();
+
+struct Particle {x: f32, speed: f32}
+
+let mut particles = arena.view::<Particle>();
+let addresses = particles.allocate_from_fn(100, |i| Particle{x: 0.0, speed: i as f32});
+for address in &addresses {
+    let mut particle = particles.get_mut(address).unwrap();
+    particle.x += particle.speed;
+}
+for (_, mut particle) in particles.iter_mut() {
+    particle.x += particle.speed;
+}
+particles.free(&addresses[0]);
+assert!(particles.get(&addresses[0]).is_none());
+assert_eq!(particles.get(Handle::from(&addresses[10])).unwrap().x, 20.0);
+assert_eq!(particles.iter().count(), 99);
+drop(particles);
+
+// the arena can be used again once the view is gone
+assert_eq!(arena.get(&addresses[99]).unwrap().x, 198.0);
+```
+ */
+
+#![forbid(missing_docs, missing_debug_implementations)]
+
+use std::any;
+use std::fmt;
+use std::rc::Rc;
+
+use super::address::Address;
+use super::arena::{Iter, IterMut, LocationGroup};
+use super::borrow::{Ref, RefMut};
+use super::error::ArenaError;
+use super::handle::{Handle, Locator};
+
+/// The entities of a single type in an arena, created by `Arena::view()`. Has the same methods as
+/// the arena for the type, without looking up the type on every call
+pub struct ArenaView<'a, T: 'static> {
+    /// Found once from the arena, which stays mutably borrowed through it
+    group: &'a Rc<LocationGroup<T>>,
+}
+
+impl<'a, T: 'static> ArenaView<'a, T> {
+    pub(crate) fn new(group: &'a Rc<LocationGroup<T>>) -> ArenaView<'a, T> {
+        ArenaView { group }
+    }
+
+    /// Finds the location an address points to, see `Arena::try_get()`
+    fn locate(&self, address: &impl Locator<T>) -> Result<(usize, usize), ArenaError> {
+        self.group.locate(address)
+    }
+
+    /// Borrow the entity at a given address or handle, see `Arena::get()`
+    #[inline]
+    pub fn get(&self, address: impl Locator<T>) -> Option<Ref<'_, T>> {
+        ArenaError::found(self.try_get(address))
+    }
+
+    /// Mutably borrow the entity at a given address or handle, see `Arena::get_mut()`
+    #[inline]
+    pub fn get_mut(&mut self, address: impl Locator<T>) -> Option<RefMut<'_, T>> {
+        ArenaError::found(self.try_get_mut(address))
+    }
+
+    /// Borrow the entity at a given address or handle, see `Arena::try_get()`
+    pub fn try_get(&self, address: impl Locator<T>) -> Result<Ref<'_, T>, ArenaError> {
+        let (index, generation) = self.locate(&address)?;
+        Rc::clone(self.group)
+            .borrow(index, generation)
+            .map_err(|error| ArenaError::from_borrow(error, index, generation))
+    }
+
+    /// Mutably borrow the entity at a given address or handle, see `Arena::try_get_mut()`
+    pub fn try_get_mut(&mut self, address: impl Locator<T>) -> Result<RefMut<'_, T>, ArenaError> {
+        let (index, generation) = self.locate(&address)?;
+        Rc::clone(self.group)
+            .borrow_mut(index, generation)
+            .map_err(|error| ArenaError::from_borrow(error, index, generation))
+    }
+
+    /// Get a new address to the entity at a given handle, see `Arena::address()`
+    pub fn address(&self, handle: Handle<T>) -> Option<Address<T>> {
+        let (index, generation) = self.group.locate(&handle).ok()?;
+        self.group.address(index, generation)
+    }
+
+    /// Adds a new entity and returns the address to that entity, see `Arena::allocate()`
+    #[inline]
+    pub fn allocate(&mut self, v: T) -> Address<T> {
+        // SAFETY: the arena is held mutably by the view
+        unsafe { self.group.allocate(v) }
+    }
+
+    /// Adds a new entity, or gives it back if there is no room left, see `Arena::try_allocate()`
+    pub fn try_allocate(&mut self, v: T) -> Result<Address<T>, T> {
+        // SAFETY: the arena is held mutably by the view
+        unsafe { self.group.try_allocate(v) }
+    }
+
+    /// Adds every entity of an iterator, see `Arena::allocate_many()`
+    pub fn allocate_many<I: IntoIterator<Item = T>>(&mut self, values: I) -> Vec<Address<T>> {
+        // SAFETY: the arena is held mutably by the view
+        unsafe { self.group.allocate_many(values) }
+    }
+
+    /// Adds `n` entities made by `f`, see `Arena::allocate_from_fn()`
+    pub fn allocate_from_fn(&mut self, n: usize, f: impl FnMut(usize) -> T) -> Vec<Address<T>> {
+        self.allocate_many((0..n).map(f))
+    }
+
+    /// Free the entity at a given address or handle, see `Arena::free()`
+    #[inline]
+    pub fn free(&self, address: impl Locator<T>) {
+        ArenaError::found(self.try_free(address));
+    }
+
+    /// Free the entity at a given address or handle, see `Arena::try_free()`
+    pub fn try_free(&self, address: impl Locator<T>) -> Result<(), ArenaError> {
+        let (index, generation) = self.locate(&address)?;
+        self.group
+            .try_free(index, generation)
+            .map_err(|error| ArenaError::from_borrow(error, index, generation))
+    }
+
+    /// Iterate over every live entity, see `Arena::iter()`
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter::new(Some(Rc::clone(self.group)))
+    }
+
+    /// Iterate over every live entity with mutable access, see `Arena::iter_mut()`
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        IterMut::new(Some(Rc::clone(self.group)))
+    }
+}
+
+impl<T> fmt::Debug for ArenaView<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("ArenaView")
+            .field(&any::type_name::<T>())
+            .finish()
+    }
+}
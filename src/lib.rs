@@ -1,3 +1,17 @@
 mod allocator;
-pub use allocator::address::Address;
-pub use allocator::arena::Arena;
+pub use allocator::address::{Address, WeakAddress};
+pub use allocator::arena::{Arena, ArenaId, Iter, IterMut};
+pub use allocator::borrow::{BorrowError, Ref, RefMut};
+pub use allocator::compact::Remap;
+pub use allocator::config::{ArenaBuilder, ArenaConfig};
+pub use allocator::ecs::{Entity, World};
+pub use allocator::error::ArenaError;
+pub use allocator::handle::{Handle, Locator};
+#[cfg(feature = "serde")]
+pub use allocator::persist::{RegisteredTypes, Registry, SavedArena};
+pub use allocator::schema::Pool;
+pub use allocator::stats::{ArenaStats, TypeStats};
+pub use allocator::storage::Storage;
+pub use allocator::sync_arena::{SyncAddress, SyncArena, SyncRef, SyncRefMut};
+pub use allocator::typed_arena::{IntoIter, TypedArena};
+pub use allocator::view::ArenaView;
@@ -3,7 +3,7 @@
 look into the `src/allocator/arena.rs` file
  */
 
-use arena_allocator::{Address, Arena};
+use arena_allocator::{Address, Arena, WeakAddress};
 use std::cell::RefCell;
 use std::rc::Rc;
 
@@ -27,25 +27,25 @@ fn demo() {
     struct Monster {
         name: String,
         health: Address<Health>,
-        target: Option<Address<Human>>,
+        target: Option<WeakAddress<Human>>,
         friend: Option<Address<Monster>>,
     }
     let mut arena = Arena::default();
     let main_enemy_health = arena.allocate(Health { value: 50 });
     let human_health = arena.allocate(Health { value: 100 });
     let main_enemy = arena.allocate(Monster {
-        name: format!("Borrow checker"),
+        name: "Borrow checker".to_string(),
         health: main_enemy_health.copy(),
         target: None,
         friend: None,
     });
     let human = arena.allocate(Human {
-        name: format!("Nader"),
+        name: "Nader".to_string(),
         health: human_health.copy(),
         enemy: main_enemy.copy(),
         enemy_stooges: vec![],
     });
-    main_enemy.get_mut().unwrap().target = Some(human.copy());
+    main_enemy.get_mut().unwrap().target = Some(human.downgrade());
     // create 5 stooges and make human aware of them
     for i in 0..5 {
         let human_ptr = human.copy();
@@ -53,7 +53,7 @@ fn demo() {
         let stooge = arena.allocate(Monster {
             name: format!("stooge #{}", i + 1),
             health: stooge_health.copy(),
-            target: Some(human_ptr.copy()),
+            target: Some(human_ptr.downgrade()),
             friend: None,
         });
         human_ptr
@@ -74,15 +74,17 @@ fn demo() {
         let human_ptr = human.copy();
         // human wants count of all enemies
         match human_ptr.get_mut() {
-            Some(h) => {
+            Some(mut h) => {
                 // make sure all references are valid
                 println!("Human sees {} stooges", h.enemy_stooges.len());
                 // human kills the last valid stooge
                 loop {
                     match h.enemy_stooges.pop() {
-                        Some(stooge_ptr) => match stooge_ptr.copy().get() {
-                            Some(stooge) => {
-                                println!("attacking a stooge with name {}", stooge.name);
+                        // the stooge is only borrowed long enough to read its name, an entity
+                        // can not be freed while it is borrowed
+                        Some(stooge_ptr) => match stooge_ptr.get().map(|s| s.name.clone()) {
+                            Some(name) => {
+                                println!("attacking a stooge with name {}", name);
                                 stooge_ptr.remove();
                                 println!("got one, now there's {} left", h.enemy_stooges.len());
                                 break;
@@ -95,7 +97,7 @@ fn demo() {
                 }
             }
             None => println!("Human died"),
-        }
+        };
     }
     {
         // Meanwhile, in a completely different scope, where pointers are unaware that they are
@@ -115,7 +117,7 @@ fn demo() {
                         println!("monster sees friend is dead is now sad");
                         // monster hits human and reduces its health
                         if let Some(human) = monster.target.as_ref().unwrap().get() {
-                            if let Some(health) = human.health.get_mut() {
+                            if let Some(mut health) = human.health.get_mut() {
                                 health.value -= 5;
                             };
                         };
@@ -131,15 +133,16 @@ fn demo() {
             }
         };
     }
+    println!("{}", arena.stats());
     println!("Demo done")
 }
 
 /// This function shows the performance difference between arena allocation and heap allocation.
-/// Adding items is not measured here but freeing them is, since freeing is where arena's really
-/// shine. Specifically, deeply self referential data types, where deallocating an entity could
-/// mean hundreds of drop() calls. In arenas, this as as simple as bumping an integer.
-/// This is done by allocating a large number of structs, and starting a timer right before they go
-/// out of scope and get freed.
+/// Both adding items and freeing them are measured, freeing being where arena's really shine.
+/// Specifically, deeply self referential data types, where deallocating an entity could mean
+/// hundreds of drop() calls. In arenas, this as as simple as bumping an integer.
+/// This is done by timing the allocation of a large number of structs, and starting another timer
+/// right before they go out of scope and get freed.
 ///
 /// If you're running this, I recommend compiling with optimizations to run this, as it runs slow
 /// without it (command is `cargo build --release && ./target/release/arena-allocator `)
@@ -162,8 +165,10 @@ fn performance() {
 
     // Arena allocator ============
     let now;
+    let allocator_allocation_elapsed;
     let mut arena = Arena::new(ITEMS_COUNT);
     {
+        let allocation_start = Instant::now();
         let mut curr: Option<Address<BigDataArena>> = None;
         let mut addresses = Vec::<Address<BigDataArena>>::with_capacity(ITEMS_COUNT);
         for _ in 0..ITEMS_COUNT {
@@ -178,6 +183,7 @@ fn performance() {
                 None => curr = Some(new_obj),
             }
         }
+        allocator_allocation_elapsed = Instant::now() - allocation_start;
         now = Instant::now();
     }
     let later = Instant::now();
@@ -186,7 +192,9 @@ fn performance() {
 
     // Heap allocation ============
     let now;
+    let box_allocation_elapsed;
     {
+        let allocation_start = Instant::now();
         let root = BigDataBox {
             data1: 0,
             val: None,
@@ -202,6 +210,7 @@ fn performance() {
             addresses.push(Rc::clone(&new_v));
             curr = new_v;
         }
+        box_allocation_elapsed = Instant::now() - allocation_start;
         now = Instant::now();
     }
     let later = Instant::now();
@@ -209,12 +218,64 @@ fn performance() {
     // ============================
 
     println!("===============Perf results===============");
-    println!("it took the allocator {:?}", allocator_elapsed);
-    println!("it took the box {:?}", box_elapsed);
+    println!(
+        "it took the allocator {:?} to allocate and {:?} to free",
+        allocator_allocation_elapsed, allocator_elapsed
+    );
+    println!(
+        "it took the box {:?} to allocate and {:?} to free",
+        box_allocation_elapsed, box_elapsed
+    );
+    println!("==========================================");
+}
+
+/// This function compares allocating entities one by one with allocating them in bulk. Bulk
+/// allocation looks up the list of the type once and makes room for all entities up front,
+/// instead of growing the list over and over. Half of the entities are freed before the second
+/// round, so that both fill freed locations first and then grow the list
+///
+/// As with `performance()`, compile with optimizations to run this
+#[allow(dead_code, unused_variables)]
+fn bulk_allocation() {
+    use std::time::Instant;
+
+    #[derive(Default)]
+    struct Monster {
+        health: i32,
+        target: Option<Address<Monster>>,
+    }
+    static ITEMS_COUNT: usize = 1_000_000;
+
+    // One by one ================
+    let mut arena = Arena::default();
+    let mut monsters: Vec<_> = (0..ITEMS_COUNT)
+        .map(|_| arena.allocate(Monster::default()))
+        .collect();
+    monsters.truncate(ITEMS_COUNT / 2);
+    let now = Instant::now();
+    for _ in 0..ITEMS_COUNT {
+        monsters.push(arena.allocate(Monster::default()));
+    }
+    let single_elapsed = Instant::now() - now;
+    // ============================
+
+    // Bulk =======================
+    let mut arena = Arena::default();
+    let mut monsters = arena.allocate_from_fn(ITEMS_COUNT, |_| Monster::default());
+    monsters.truncate(ITEMS_COUNT / 2);
+    let now = Instant::now();
+    monsters.extend(arena.allocate_many((0..ITEMS_COUNT).map(|_| Monster::default())));
+    let bulk_elapsed = Instant::now() - now;
+    // ============================
+
+    println!("===============Bulk results===============");
+    println!("it took one by one allocation {:?}", single_elapsed);
+    println!("it took bulk allocation {:?}", bulk_elapsed);
     println!("==========================================");
 }
 
 fn main() {
     performance();
+    bulk_allocation();
     demo();
 }